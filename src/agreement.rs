@@ -0,0 +1,277 @@
+use std::collections::BTreeMap as Map;
+use std::collections::BTreeSet as Set;
+
+/// The labels given to a single commit, keyed by rater.
+pub type Unit = Map<String, String>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Agreement {
+    /// Cohen's kappa for every pair of raters, `None` if it is undefined for the pair.
+    pub cohen: Vec<(String, String, Option<f64>)>,
+    pub fleiss: Option<f64>,
+    pub krippendorff: Option<f64>,
+}
+
+impl Agreement {
+    pub fn from_units(units: &[Unit]) -> Self {
+        let raters: Vec<&String> = units
+            .iter()
+            .flat_map(|unit| unit.keys())
+            .collect::<Set<_>>()
+            .into_iter()
+            .collect();
+        let mut cohen = Vec::new();
+        for (idx, first) in raters.iter().enumerate() {
+            for second in &raters[idx + 1..] {
+                cohen.push((
+                    first.to_string(),
+                    second.to_string(),
+                    cohen_kappa(units, first, second),
+                ));
+            }
+        }
+        Agreement {
+            cohen,
+            fleiss: fleiss_kappa(units, &raters),
+            krippendorff: krippendorff_alpha(units),
+        }
+    }
+
+    /// The mean of all defined pairwise Cohen's kappas.
+    pub fn mean_cohen(&self) -> Option<f64> {
+        let defined: Vec<f64> = self.cohen.iter().filter_map(|(_, _, k)| *k).collect();
+        if defined.is_empty() {
+            None
+        } else {
+            Some(defined.iter().sum::<f64>() / defined.len() as f64)
+        }
+    }
+}
+
+pub fn format_coefficient(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.3}", value),
+        None => "n/a".to_string(),
+    }
+}
+
+/// Cohen's kappa over all units which have been rated by both raters.
+fn cohen_kappa(units: &[Unit], first: &str, second: &str) -> Option<f64> {
+    let pairs: Vec<(&String, &String)> = units
+        .iter()
+        .filter_map(|unit| Some((unit.get(first)?, unit.get(second)?)))
+        .collect();
+    if pairs.is_empty() {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let observed = pairs.iter().filter(|(a, b)| a == b).count() as f64 / n;
+    let mut first_counts: Map<&String, f64> = Map::new();
+    let mut second_counts: Map<&String, f64> = Map::new();
+    for (a, b) in &pairs {
+        *first_counts.entry(a).or_default() += 1.0;
+        *second_counts.entry(b).or_default() += 1.0;
+    }
+    let expected = first_counts
+        .iter()
+        .map(|(label, count)| count / n * second_counts.get(label).unwrap_or(&0.0) / n)
+        .sum::<f64>();
+    if expected >= 1.0 {
+        return None;
+    }
+    Some((observed - expected) / (1.0 - expected))
+}
+
+/// Fleiss' kappa over all units which have been rated by every rater.
+fn fleiss_kappa(units: &[Unit], raters: &[&String]) -> Option<f64> {
+    let m = raters.len();
+    if m < 2 {
+        return None;
+    }
+    let complete: Vec<&Unit> = units.iter().filter(|unit| unit.len() == m).collect();
+    if complete.is_empty() {
+        return None;
+    }
+    let n = complete.len() as f64;
+    let mut totals: Map<&String, f64> = Map::new();
+    let mut mean_agreement = 0.0;
+    for unit in &complete {
+        let counts = label_counts(unit);
+        let squares: f64 = counts.values().map(|count| count * count).sum();
+        mean_agreement += (squares - m as f64) / (m * (m - 1)) as f64;
+        for (label, count) in counts {
+            *totals.entry(label).or_default() += count;
+        }
+    }
+    mean_agreement /= n;
+    let expected: f64 = totals
+        .values()
+        .map(|total| {
+            let proportion = total / (n * m as f64);
+            proportion * proportion
+        })
+        .sum();
+    if expected >= 1.0 {
+        return None;
+    }
+    Some((mean_agreement - expected) / (1.0 - expected))
+}
+
+/// Krippendorff's alpha for nominal data, units with a single rating are not pairable and skipped.
+fn krippendorff_alpha(units: &[Unit]) -> Option<f64> {
+    let mut observed_disagreement = 0.0;
+    let mut totals: Map<&String, f64> = Map::new();
+    for unit in units.iter().filter(|unit| unit.len() >= 2) {
+        let m = unit.len() as f64;
+        let counts = label_counts(unit);
+        let squares: f64 = counts.values().map(|count| count * count).sum();
+        observed_disagreement += (m * m - squares) / (m - 1.0);
+        for (label, count) in counts {
+            *totals.entry(label).or_default() += count;
+        }
+    }
+    let n: f64 = totals.values().sum();
+    let expected_disagreement = n * n - totals.values().map(|total| total * total).sum::<f64>();
+    if n <= 1.0 || expected_disagreement == 0.0 {
+        return None;
+    }
+    Some(1.0 - (n - 1.0) * observed_disagreement / expected_disagreement)
+}
+
+fn label_counts(unit: &Unit) -> Map<&String, f64> {
+    let mut counts = Map::new();
+    for label in unit.values() {
+        *counts.entry(label).or_default() += 1.0;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds units from rows of labels, one column per rater, `None` marks a missing rating.
+    fn units(rows: &[&[Option<&str>]]) -> Vec<Unit> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter_map(|(rater, label)| {
+                        Some((format!("r{}", rater), (*label)?.to_string()))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn assert_close(value: Option<f64>, expected: f64) {
+        let value = value.expect("coefficient is undefined");
+        assert!(
+            (value - expected).abs() < 5e-4,
+            "{} is not {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn cohen_kappa_of_two_raters() {
+        // 20 yes/yes, 5 yes/no, 10 no/yes and 15 no/no, p_o = 0.7 and p_e = 0.5
+        let mut rows: Vec<&[Option<&str>]> = Vec::new();
+        rows.extend(vec![&[Some("yes"), Some("yes")][..]; 20]);
+        rows.extend(vec![&[Some("yes"), Some("no")][..]; 5]);
+        rows.extend(vec![&[Some("no"), Some("yes")][..]; 10]);
+        rows.extend(vec![&[Some("no"), Some("no")][..]; 15]);
+        assert_close(cohen_kappa(&units(&rows), "r0", "r1"), 0.4);
+    }
+
+    #[test]
+    fn cohen_kappa_skips_units_missing_a_rater() {
+        let units = units(&[
+            &[Some("yes"), Some("yes")],
+            &[Some("no"), Some("no")],
+            &[Some("yes"), Some("no")],
+            &[Some("no"), Some("no")],
+            &[Some("yes"), None],
+            &[None, Some("yes")],
+        ]);
+        // p_o = 0.75, p_e = 0.5 * 0.25 + 0.5 * 0.75 = 0.5
+        assert_close(cohen_kappa(&units, "r0", "r1"), 0.5);
+        assert_eq!(cohen_kappa(&units[4..], "r0", "r1"), None);
+    }
+
+    #[test]
+    fn fleiss_kappa_of_fourteen_raters() {
+        // the example of Fleiss (1971) as given on Wikipedia, 14 raters sort 10 subjects into
+        // five categories
+        let counts = [
+            [0, 0, 0, 0, 14],
+            [0, 2, 6, 4, 2],
+            [0, 0, 3, 5, 6],
+            [0, 3, 9, 2, 0],
+            [2, 2, 8, 1, 1],
+            [7, 7, 0, 0, 0],
+            [3, 2, 6, 3, 0],
+            [2, 5, 3, 2, 2],
+            [6, 5, 2, 1, 0],
+            [0, 2, 2, 3, 7],
+        ];
+        let labels = ["1", "2", "3", "4", "5"];
+        let units: Vec<Unit> = counts
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(labels.iter())
+                    .flat_map(|(count, label)| vec![*label; *count])
+                    .enumerate()
+                    .map(|(rater, label)| (format!("r{:02}", rater), label.to_string()))
+                    .collect()
+            })
+            .collect();
+        let raters: Vec<String> = (0..14).map(|rater| format!("r{:02}", rater)).collect();
+        let raters: Vec<&String> = raters.iter().collect();
+        assert_close(fleiss_kappa(&units, &raters), 0.210);
+    }
+
+    #[test]
+    fn krippendorff_alpha_with_missing_ratings() {
+        // the nominal example of Krippendorff (2011), four observers rate twelve units
+        let data: [[Option<&str>; 4]; 12] = [
+            [Some("1"), Some("1"), None, Some("1")],
+            [Some("2"), Some("2"), Some("3"), Some("2")],
+            [Some("3"), Some("3"), Some("3"), Some("3")],
+            [Some("3"), Some("3"), Some("3"), Some("3")],
+            [Some("2"), Some("2"), Some("2"), Some("2")],
+            [Some("1"), Some("2"), Some("3"), Some("4")],
+            [Some("4"), Some("4"), Some("4"), Some("4")],
+            [Some("1"), Some("1"), Some("2"), Some("1")],
+            [Some("2"), Some("2"), Some("2"), Some("2")],
+            [None, Some("5"), Some("5"), Some("5")],
+            [None, None, Some("1"), Some("1")],
+            [None, Some("3"), None, None],
+        ];
+        let rows: Vec<&[Option<&str>]> = data.iter().map(|row| &row[..]).collect();
+        assert_close(krippendorff_alpha(&units(&rows)), 0.743);
+    }
+
+    #[test]
+    fn single_label_is_undefined() {
+        let units = units(&[
+            &[Some("yes"), Some("yes"), Some("yes")],
+            &[Some("yes"), Some("yes"), Some("yes")],
+        ]);
+        let agreement = Agreement::from_units(&units);
+        assert!(agreement.cohen.iter().all(|(_, _, kappa)| kappa.is_none()));
+        assert_eq!(agreement.mean_cohen(), None);
+        assert_eq!(agreement.fleiss, None);
+        assert_eq!(agreement.krippendorff, None);
+    }
+
+    #[test]
+    fn no_units_are_undefined() {
+        let agreement = Agreement::from_units(&[]);
+        assert!(agreement.cohen.is_empty());
+        assert_eq!(agreement.fleiss, None);
+        assert_eq!(agreement.krippendorff, None);
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Content-addressed storage of fetched commit messages and diffs.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// `$XDG_CACHE_HOME/igitt`, falling back to `~/.cache/igitt`.
+pub fn default_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .map(|cache| cache.join("igitt"))
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Cache { dir }
+    }
+
+    /// Entries are stored under the sha256 of origin and commit hash.
+    fn entry(&self, origin: &str, commit: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.input(origin.as_bytes());
+        hasher.input(b"\n");
+        hasher.input(commit.as_bytes());
+        let key = hasher
+            .result()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        self.dir.join(&key[..2]).join(&key[2..])
+    }
+
+    pub fn get(&self, origin: &str, commit: &str) -> Option<(String, String)> {
+        let entry = self.entry(origin, commit);
+        let message = fs::read_to_string(entry.join("message")).ok()?;
+        let diff = fs::read_to_string(entry.join("diff")).ok()?;
+        Some((message, diff))
+    }
+
+    pub fn put(&self, origin: &str, commit: &str, message: &str, diff: &str) -> Result<()> {
+        let entry = self.entry(origin, commit);
+        fs::create_dir_all(&entry)?;
+        for (name, content) in &[("diff", diff), ("message", message)] {
+            // written to a temporary file first, so that interrupted writes are never read
+            let tmp = entry.join(format!(".{}.tmp", name));
+            fs::write(&tmp, content)?;
+            fs::rename(&tmp, entry.join(name))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use crate::keys::Keys;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap as Map;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    #[serde(alias = "forgejo")]
+    Gitea,
+    Bitbucket,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ForgeConfig {
+    pub kind: ForgeKind,
+    /// Base url of the API, derived from the host if not given.
+    #[serde(default)]
+    pub api: Option<String>,
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Tokens used for all GitHub and GitLab hosts which don't declare their own.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Tokens {
+    #[serde(default)]
+    pub github: Option<String>,
+    #[serde(default)]
+    pub gitlab: Option<String>,
+}
+
+/// The per-user configuration of igitt.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Maps hosts to the forge running on them.
+    #[serde(default)]
+    pub forges: Map<String, ForgeConfig>,
+    #[serde(default)]
+    pub tokens: Tokens,
+    #[serde(default)]
+    pub keys: Keys,
+}
+
+/// `$XDG_CONFIG_HOME/igitt/config.yaml`, falling back to `~/.config/igitt/config.yaml`.
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|config| config.join("igitt").join("config.yaml"))
+}
+
+impl Config {
+    /// Loads the configuration from the given path, or from the default path if it exists.
+    pub fn load(path: Option<&str>) -> Result<Config> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let file = File::open(&path)
+            .with_context(|| format!("could not open config file {}", path.display()))?;
+        serde_yaml::from_reader(file)
+            .with_context(|| format!("could not parse config file {}", path.display()))
+    }
+}
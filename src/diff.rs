@@ -0,0 +1,719 @@
+use cursive::theme::{BaseColor, Color, Effect, PaletteColor, Style, Theme};
+use cursive::utils::span::SpannedString;
+use std::path::Path;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The narrowest screen on which the side-by-side view is shown, narrower screens fall back to
+/// the unified view.
+pub const SIDE_BY_SIDE_MIN_WIDTH: usize = 120;
+
+/// Files which are rarely of interest for a rating and therefore collapsed from the start.
+const GENERATED_FILES: [&str; 11] = [
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "Gemfile.lock",
+    "composer.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+];
+const GENERATED_SUFFIXES: [&str; 5] = [".min.js", ".min.css", ".pb.go", "_pb2.py", ".snap"];
+
+/// Distance of the tab stops in the side-by-side view.
+const TAB_WIDTH: usize = 4;
+
+/// A file changed by a diff.
+pub struct File {
+    /// The lines preceding the first hunk, e.g. `diff --git` and `index`.
+    pub header: Vec<String>,
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+}
+
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@` line.
+    pub header: String,
+    pub lines: Vec<Line>,
+}
+
+/// A line of a hunk without its `+`, `-` or ` ` prefix.
+pub enum Line {
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Splits a unified diff into its files and hunks.
+pub fn parse(diff: &str) -> Vec<File> {
+    let mut files: Vec<File> = Vec::new();
+    let mut in_header = false;
+    for line in diff.lines() {
+        if line.starts_with("diff ") || files.is_empty() {
+            files.push(File {
+                header: Vec::new(),
+                path: match line.strip_prefix("diff ") {
+                    Some(rest) => rest.rsplit(" b/").next().unwrap_or_default().to_string(),
+                    None => String::new(),
+                },
+                hunks: Vec::new(),
+            });
+            in_header = true;
+        }
+        let file = files.last_mut().unwrap();
+        if line.starts_with("@@") {
+            in_header = false;
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+        } else if in_header {
+            // the new path is preferred, unless the file has been deleted
+            if let Some(path) = line
+                .strip_prefix("+++ ")
+                .filter(|path| *path != "/dev/null")
+            {
+                file.path = path.get(2..).unwrap_or(path).to_string();
+            } else if let Some(path) = line
+                .strip_prefix("--- ")
+                .filter(|path| *path != "/dev/null")
+            {
+                file.path = path.get(2..).unwrap_or(path).to_string();
+            }
+            file.header.push(line.to_string());
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            let parsed = if let Some(rest) = line.strip_prefix('+') {
+                Line::Added(rest.to_string())
+            } else if let Some(rest) = line.strip_prefix('-') {
+                Line::Removed(rest.to_string())
+            } else {
+                Line::Context(line.strip_prefix(' ').unwrap_or(line).to_string())
+            };
+            hunk.lines.push(parsed);
+        }
+    }
+    files
+}
+
+impl File {
+    pub fn added(&self) -> usize {
+        self.count(|line| matches!(line, Line::Added(_)))
+    }
+
+    pub fn removed(&self) -> usize {
+        self.count(|line| matches!(line, Line::Removed(_)))
+    }
+
+    fn count(&self, predicate: impl Fn(&Line) -> bool) -> usize {
+        self.hunks
+            .iter()
+            .flat_map(|hunk| hunk.lines.iter())
+            .filter(|line| predicate(line))
+            .count()
+    }
+
+    /// Lockfiles and generated code, which are collapsed from the start.
+    pub fn is_generated(&self) -> bool {
+        let name = Path::new(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        GENERATED_FILES.contains(&name.as_str())
+            || GENERATED_SUFFIXES
+                .iter()
+                .any(|suffix| name.ends_with(suffix))
+    }
+}
+
+/// A rendered diff along with the line every file starts at.
+pub struct Rendered {
+    pub text: SpannedString<Style>,
+    pub offsets: Vec<usize>,
+}
+
+/// Styles of the diff, built from the base colors of the terminal and the palette of the theme,
+/// so that they suit light as well as dark terminals.
+struct Styles {
+    file_header: Style,
+    hunk_header: Style,
+    added: Style,
+    removed: Style,
+    context: Style,
+}
+
+impl Default for Styles {
+    fn default() -> Self {
+        Styles {
+            file_header: Style::from(PaletteColor::TitlePrimary).combine(Effect::Bold),
+            hunk_header: Style::from(Color::Dark(BaseColor::Cyan)),
+            added: Style::from(Color::Dark(BaseColor::Green)),
+            removed: Style::from(Color::Dark(BaseColor::Red)),
+            context: Style::from(PaletteColor::Primary),
+        }
+    }
+}
+
+/// Whether a line of code has been added, removed or left as it was.
+#[derive(Clone, Copy)]
+enum Change {
+    Added,
+    Removed,
+    Context,
+}
+
+/// Collects the rendered text and keeps track of the lines it spans.
+#[derive(Default)]
+struct Renderer {
+    text: SpannedString<Style>,
+    offsets: Vec<usize>,
+    lines: usize,
+    counted: usize,
+}
+
+impl Renderer {
+    fn start_file(&mut self) {
+        self.lines += self.text.source()[self.counted..].matches('\n').count();
+        self.counted = self.text.source().len();
+        self.offsets.push(self.lines);
+    }
+
+    /// Renders the header of a file, the return value tells whether its hunks are to be rendered.
+    fn header(&mut self, file: &File, collapsed: bool, styles: &Styles) -> bool {
+        self.start_file();
+        if collapsed {
+            self.text.append_styled(
+                format!(
+                    "{} (collapsed, +{} -{})\n",
+                    file.path,
+                    file.added(),
+                    file.removed()
+                ),
+                styles.file_header,
+            );
+            return false;
+        }
+        for line in &file.header {
+            self.text
+                .append_styled(format!("{}\n", line), styles.file_header);
+        }
+        true
+    }
+
+    fn finish(self) -> Rendered {
+        Rendered {
+            text: self.text,
+            offsets: self.offsets,
+        }
+    }
+}
+
+/// Renders the diff like `git diff`, collapsed files are reduced to a single line.
+pub fn unified(files: &[File], collapsed: &[bool], theme: &Theme) -> Rendered {
+    let styles = Styles::default();
+    let mut code = syntax::Code::new(theme);
+    let mut renderer = Renderer::default();
+    for (idx, file) in files.iter().enumerate() {
+        let is_collapsed = collapsed.get(idx).copied().unwrap_or(false);
+        if !renderer.header(file, is_collapsed, &styles) {
+            continue;
+        }
+        code.file(&file.path);
+        for hunk in &file.hunks {
+            code.hunk();
+            renderer
+                .text
+                .append_styled(format!("{}\n", hunk.header), styles.hunk_header);
+            for line in &hunk.lines {
+                let text = &mut renderer.text;
+                match line {
+                    Line::Added(line) => {
+                        text.append_styled("+", styles.added.combine(Effect::Bold));
+                        code.append(text, &format!("{}\n", line), styles.added, Change::Added);
+                    }
+                    Line::Removed(line) => {
+                        text.append_styled("-", styles.removed.combine(Effect::Bold));
+                        code.append(
+                            text,
+                            &format!("{}\n", line),
+                            styles.removed,
+                            Change::Removed,
+                        );
+                    }
+                    Line::Context(line) => {
+                        code.append(
+                            text,
+                            &format!(" {}\n", line),
+                            styles.context,
+                            Change::Context,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    renderer.finish()
+}
+
+/// Renders the diff in two columns with the old code on the left and the new code on the right,
+/// removed and added lines are aligned next to each other.
+pub fn side_by_side(files: &[File], collapsed: &[bool], width: usize) -> Rendered {
+    let styles = Styles::default();
+    let column = width.saturating_sub(3) / 2;
+    let mut renderer = Renderer::default();
+    for (idx, file) in files.iter().enumerate() {
+        let is_collapsed = collapsed.get(idx).copied().unwrap_or(false);
+        if !renderer.header(file, is_collapsed, &styles) {
+            continue;
+        }
+        let text = &mut renderer.text;
+        for hunk in &file.hunks {
+            text.append_styled(format!("{}\n", hunk.header), styles.hunk_header);
+            let mut removed_lines = Vec::new();
+            let mut added_lines = Vec::new();
+            for line in &hunk.lines {
+                match line {
+                    Line::Removed(line) => removed_lines.push(line.as_str()),
+                    Line::Added(line) => added_lines.push(line.as_str()),
+                    Line::Context(line) => {
+                        pair_rows(text, &removed_lines, &added_lines, &styles, column);
+                        removed_lines.clear();
+                        added_lines.clear();
+                        row(
+                            text,
+                            Some(line),
+                            Some(line),
+                            styles.context,
+                            styles.context,
+                            column,
+                        );
+                    }
+                }
+            }
+            pair_rows(text, &removed_lines, &added_lines, &styles, column);
+        }
+    }
+    renderer.finish()
+}
+
+/// Places a run of removed lines next to the added lines replacing them.
+fn pair_rows(
+    text: &mut SpannedString<Style>,
+    removed_lines: &[&str],
+    added_lines: &[&str],
+    styles: &Styles,
+    column: usize,
+) {
+    for idx in 0..removed_lines.len().max(added_lines.len()) {
+        row(
+            text,
+            removed_lines.get(idx).copied(),
+            added_lines.get(idx).copied(),
+            styles.removed,
+            styles.added,
+            column,
+        );
+    }
+}
+
+/// Appends a row of both columns, lines longer than a column are wrapped within it.
+fn row(
+    text: &mut SpannedString<Style>,
+    left: Option<&str>,
+    right: Option<&str>,
+    left_style: Style,
+    right_style: Style,
+    column: usize,
+) {
+    let left = wrap(left.unwrap_or_default(), column);
+    let right = wrap(right.unwrap_or_default(), column);
+    for idx in 0..left.len().max(right.len()) {
+        let left_part = left.get(idx).map_or("", String::as_str);
+        let right_part = right.get(idx).map_or("", String::as_str);
+        // padded by the width on screen, wide characters take up two columns
+        let padding = " ".repeat(column.saturating_sub(left_part.width()));
+        text.append_styled(format!("{}{}", left_part, padding), left_style);
+        text.append_styled(" │ ", PaletteColor::Secondary);
+        text.append_styled(format!("{}\n", right_part), right_style);
+    }
+}
+
+/// Replaces tabs by spaces up to the next tab stop, so that the width of the line is known.
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::new();
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = TAB_WIDTH - expanded.width() % TAB_WIDTH;
+            expanded.push_str(&" ".repeat(spaces));
+        } else {
+            expanded.push(c);
+        }
+    }
+    expanded
+}
+
+/// Splits a line into parts which fit into a column of the given width on screen.
+fn wrap(line: &str, column: usize) -> Vec<String> {
+    let line = expand_tabs(line);
+    if line.is_empty() || column == 0 {
+        return vec![String::new()];
+    }
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    for c in line.chars() {
+        // a character wider than the column still gets a part of its own
+        if part.width() + c.width().unwrap_or(0) > column && !part.is_empty() {
+            parts.push(std::mem::take(&mut part));
+        }
+        part.push(c);
+    }
+    parts.push(part);
+    parts
+}
+
+#[cfg(feature = "syntax-highlighting")]
+mod syntax {
+    use super::Change;
+    use cursive::theme::{BaseColor, Color, ColorStyle, PaletteColor, Style, Theme};
+    use cursive::utils::span::SpannedString;
+    use std::path::Path;
+    use syntect::highlighting::{self, HighlightIterator, HighlightState, Highlighter, ThemeSet};
+    use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+
+    /// Highlights the code of the changed files according to their language.
+    pub struct Code {
+        syntaxes: SyntaxSet,
+        theme: highlighting::Theme,
+        extension: Option<String>,
+        state: Option<(ParseState, HighlightState)>,
+        /// Backgrounds of added and removed lines, whose foreground is taken by the syntax.
+        added: Color,
+        removed: Color,
+    }
+
+    /// Light views need a light syntax theme, everything else is treated as dark.
+    fn is_light(theme: &Theme) -> bool {
+        match theme.palette[PaletteColor::View] {
+            Color::Dark(BaseColor::White) | Color::Light(BaseColor::White) => true,
+            Color::Light(BaseColor::Yellow) | Color::Light(BaseColor::Cyan) => true,
+            Color::Rgb(r, g, b) => u32::from(r) + u32::from(g) + u32::from(b) > 3 * 128,
+            _ => false,
+        }
+    }
+
+    impl Code {
+        pub fn new(theme: &Theme) -> Self {
+            let mut themes = ThemeSet::load_defaults().themes;
+            let (name, added, removed) = if is_light(theme) {
+                (
+                    "InspiredGitHub",
+                    Color::Rgb(0xe6, 0xff, 0xec),
+                    Color::Rgb(0xff, 0xeb, 0xe9),
+                )
+            } else {
+                (
+                    "base16-ocean.dark",
+                    Color::Rgb(0x1e, 0x3a, 0x28),
+                    Color::Rgb(0x4b, 0x1f, 0x24),
+                )
+            };
+            Code {
+                syntaxes: SyntaxSet::load_defaults_newlines(),
+                theme: themes.remove(name).unwrap_or_default(),
+                extension: None,
+                state: None,
+                added,
+                removed,
+            }
+        }
+
+        pub fn file(&mut self, path: &str) {
+            self.extension = Path::new(path)
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_string());
+            self.hunk();
+        }
+
+        /// A hunk starts somewhere in the middle of a file, the parser state is reset for it.
+        pub fn hunk(&mut self) {
+            self.state = None;
+        }
+
+        pub fn append(
+            &mut self,
+            text: &mut SpannedString<Style>,
+            line: &str,
+            fallback: Style,
+            change: Change,
+        ) {
+            let highlighter = Highlighter::new(&self.theme);
+            if self.state.is_none() {
+                let syntaxes = &self.syntaxes;
+                self.state = self
+                    .extension
+                    .as_ref()
+                    .and_then(|extension| syntaxes.find_syntax_by_extension(extension))
+                    .map(|syntax| {
+                        (
+                            ParseState::new(syntax),
+                            HighlightState::new(&highlighter, ScopeStack::new()),
+                        )
+                    });
+            }
+            let (parse_state, highlight_state) = match &mut self.state {
+                Some(state) => state,
+                None => return text.append_styled(line, fallback),
+            };
+            let ops = match parse_state.parse_line(line, &self.syntaxes) {
+                Ok(ops) => ops,
+                Err(_) => return text.append_styled(line, fallback),
+            };
+            // the syntax takes the foreground, so the change is shown by the background
+            let background = match change {
+                Change::Added => Some(self.added),
+                Change::Removed => Some(self.removed),
+                Change::Context => None,
+            };
+            for (style, part) in HighlightIterator::new(highlight_state, &ops, line, &highlighter) {
+                let color = style.foreground;
+                let color = Color::Rgb(color.r, color.g, color.b);
+                match background {
+                    Some(background) => {
+                        text.append_styled(part, ColorStyle::new(color, background))
+                    }
+                    None => text.append_styled(part, color),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "syntax-highlighting"))]
+mod syntax {
+    use super::Change;
+    use cursive::theme::{Style, Theme};
+    use cursive::utils::span::SpannedString;
+
+    /// Without syntax highlighting code is shown in the color of its line.
+    pub struct Code;
+
+    impl Code {
+        pub fn new(_theme: &Theme) -> Self {
+            Code
+        }
+
+        pub fn file(&mut self, _path: &str) {}
+
+        pub fn hunk(&mut self) {}
+
+        pub fn append(
+            &mut self,
+            text: &mut SpannedString<Style>,
+            line: &str,
+            fallback: Style,
+            _change: Change,
+        ) {
+            text.append_styled(line, fallback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    old();
++    new();
+ }
+diff --git a/src/old.rs b/src/new.rs
+similarity index 90%
+rename from src/old.rs
+rename to src/new.rs
+--- a/src/old.rs
++++ b/src/new.rs
+@@ -1 +1 @@
+-mod old;
++mod new;
+diff --git a/added.txt b/added.txt
+new file mode 100644
+--- /dev/null
++++ b/added.txt
+@@ -0,0 +1,2 @@
++first
++second
+diff --git a/removed.txt b/removed.txt
+deleted file mode 100644
+--- a/removed.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+diff --git a/logo.png b/logo.png
+index 3333333..4444444 100644
+Binary files a/logo.png and b/logo.png differ
+diff --git a/Cargo.lock b/Cargo.lock
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -1 +1 @@
+-version = 1
++version = 2
+";
+
+    fn paths(files: &[File]) -> Vec<&str> {
+        files.iter().map(|file| file.path.as_str()).collect()
+    }
+
+    /// The rendered line every file starts at.
+    fn first_lines(rendered: &Rendered) -> Vec<&str> {
+        let lines: Vec<&str> = rendered.text.source().lines().collect();
+        rendered
+            .offsets
+            .iter()
+            .map(|offset| lines[*offset])
+            .collect()
+    }
+
+    #[test]
+    fn files_of_a_diff() {
+        let files = parse(DIFF);
+        assert_eq!(
+            paths(&files),
+            vec![
+                "src/lib.rs",
+                "src/new.rs",
+                "added.txt",
+                "removed.txt",
+                "logo.png",
+                "Cargo.lock"
+            ]
+        );
+        let counts: Vec<(usize, usize)> = files
+            .iter()
+            .map(|file| (file.added(), file.removed()))
+            .collect();
+        assert_eq!(counts, vec![(1, 1), (1, 1), (2, 0), (0, 1), (0, 0), (1, 1)]);
+        assert!(files[4].hunks.is_empty());
+        assert_eq!(files[4].header.len(), 3);
+        let generated: Vec<bool> = files.iter().map(File::is_generated).collect();
+        assert_eq!(generated, vec![false, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn lines_of_a_hunk() {
+        let files = parse(DIFF);
+        let hunk = &files[0].hunks[0];
+        assert_eq!(hunk.header, "@@ -1,3 +1,3 @@");
+        let lines: Vec<String> = hunk
+            .lines
+            .iter()
+            .map(|line| match line {
+                Line::Context(line) => format!(" {}", line),
+                Line::Added(line) => format!("+{}", line),
+                Line::Removed(line) => format!("-{}", line),
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![" fn main() {", "-    old();", "+    new();", " }"]
+        );
+    }
+
+    #[test]
+    fn unified_offsets_point_at_the_file_headers() {
+        let files = parse(DIFF);
+        let rendered = unified(&files, &[false; 6], &Theme::default());
+        assert_eq!(rendered.text.source(), DIFF);
+        let headers: Vec<String> = files.iter().map(|file| file.header[0].clone()).collect();
+        assert_eq!(first_lines(&rendered), headers);
+    }
+
+    #[test]
+    fn collapsed_files_take_a_single_line() {
+        let files = parse(DIFF);
+        let mut collapsed: Vec<bool> = files.iter().map(File::is_generated).collect();
+        collapsed[1] = true;
+        let rendered = unified(&files, &collapsed, &Theme::default());
+        let first = first_lines(&rendered);
+        assert_eq!(first[1], "src/new.rs (collapsed, +1 -1)");
+        assert_eq!(first[2], "diff --git a/added.txt b/added.txt");
+        assert_eq!(first[5], "Cargo.lock (collapsed, +1 -1)");
+        assert_eq!(
+            rendered.text.source().lines().count(),
+            rendered.offsets[5] + 1
+        );
+    }
+
+    #[test]
+    fn text_before_the_first_file() {
+        let files = parse("From 0123abc\n\ndiff --git a/a b/a\n");
+        assert_eq!(paths(&files), vec!["", "a"]);
+    }
+
+    /// The left and the right column of every row of a side-by-side diff.
+    fn columns(rendered: &Rendered) -> Vec<(&str, &str)> {
+        rendered
+            .text
+            .source()
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, " │ ");
+                Some((parts.next()?, parts.next()?))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn side_by_side_pairs_removed_and_added_lines() {
+        let files = parse(
+            "diff --git a/a b/a\n@@ -1,4 +1,3 @@\n same\n-old 1\n-old 2\n+new 1\n end\n-gone\n",
+        );
+        let rendered = side_by_side(&files, &[false], 23);
+        let rows: Vec<(&str, &str)> = columns(&rendered)
+            .into_iter()
+            .map(|(left, right)| (left.trim_end(), right))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("same", "same"),
+                ("old 1", "new 1"),
+                ("old 2", ""),
+                ("end", "end"),
+                ("gone", ""),
+            ]
+        );
+        assert_eq!(first_lines(&rendered), vec!["diff --git a/a b/a"]);
+    }
+
+    #[test]
+    fn side_by_side_columns_have_the_same_width_on_screen() {
+        let files = parse(
+            "diff --git a/a b/a\n@@ -1,4 +1,4 @@\n-名前を変更する関数\n+rename 😀 the function\n-\tindented\n+x\tyz\n",
+        );
+        let rendered = side_by_side(&files, &[false], 23);
+        let columns = columns(&rendered);
+        assert!(columns.len() > 4, "long lines are wrapped");
+        for (left, right) in columns {
+            assert_eq!(left.width(), 10, "'{}' is misaligned", left);
+            assert!(right.width() <= 10, "'{}' is too wide", right);
+            assert!(!left.contains('\t') && !right.contains('\t'));
+        }
+    }
+
+    #[test]
+    fn tabs_are_expanded_to_tab_stops() {
+        assert_eq!(expand_tabs("\tx"), "    x");
+        assert_eq!(expand_tabs("ab\tx"), "ab  x");
+        assert_eq!(expand_tabs("名\tx"), "名  x");
+        assert_eq!(wrap("名前を", 3), vec!["名", "前", "を"]);
+        assert_eq!(wrap("名", 1), vec!["名"]);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{Commit, Document, Settings};
+use std::fmt::Write;
+
+/// A double-quoted YAML scalar, JSON strings are valid YAML once DEL and the C1 controls, which
+/// JSON leaves as they are, have been escaped.
+fn quote(text: &str) -> String {
+    let json = serde_json::to_string(text).expect("strings can always be serialized");
+    let mut quoted = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '\u{7f}' => quoted.push_str("\\x7F"),
+            '\u{80}'..='\u{9f}' => write!(quoted, "\\u{:04X}", c as u32).unwrap(),
+            _ => quoted.push(c),
+        }
+    }
+    quoted
+}
+
+fn write_settings(out: &mut String, settings: &Settings) {
+    out.push_str("igitt:\n  labels:\n");
+    for label in settings.labels.iter() {
+        writeln!(out, "    - id: {}", quote(&label.id)).unwrap();
+        writeln!(out, "      description: {}", quote(&label.description)).unwrap();
+        if let Some(key) = label.key {
+            writeln!(out, "      key: {}", quote(&key.to_string())).unwrap();
+        }
+        writeln!(out, "      positive: {}", label.positive).unwrap();
+    }
+    if !settings.patterns.is_empty() {
+        out.push_str("  patterns:\n");
+        for (keyword, patterns) in &settings.patterns {
+            writeln!(out, "    {}:", quote(keyword)).unwrap();
+            for pattern in patterns {
+                writeln!(out, "      - {}", quote(pattern)).unwrap();
+            }
+        }
+    }
+}
+
+fn write_commit(out: &mut String, commit: &Commit) {
+    writeln!(out, "  - origin: {}", quote(&commit.origin)).unwrap();
+    writeln!(out, "    moved: {}", commit.moved).unwrap();
+    if let Some(reason) = &commit.moved_reason {
+        writeln!(out, "    moved_reason: {}", quote(reason)).unwrap();
+    }
+    writeln!(out, "    commit: {}", quote(&commit.commit)).unwrap();
+    writeln!(out, "    section: {}", quote(&commit.section)).unwrap();
+    writeln!(out, "    time: {}", quote(&commit.time)).unwrap();
+    if commit.rating.is_empty() {
+        out.push_str("    rating: {}\n");
+    } else {
+        out.push_str("    rating:\n");
+        for (rater, rating) in &commit.rating {
+            writeln!(out, "      {}:", quote(rater)).unwrap();
+            if let Some(label) = &rating.label {
+                writeln!(out, "        label: {}", quote(label)).unwrap();
+            }
+            writeln!(out, "        comment: {}", quote(&rating.comment)).unwrap();
+        }
+    }
+    if let Some(adjudication) = &commit.adjudication {
+        out.push_str("    adjudication:\n");
+        writeln!(
+            out,
+            "      adjudicator: {}",
+            quote(&adjudication.adjudicator)
+        )
+        .unwrap();
+        writeln!(out, "      label: {}", quote(&adjudication.label)).unwrap();
+        writeln!(out, "      comment: {}", quote(&adjudication.comment)).unwrap();
+    }
+}
+
+/// Writes the document as YAML, the same document is always written the same way so that saving
+/// only changes the lines of the commits which have been changed.
+/// Keys are written in a fixed order, every string is double-quoted and commits are separated by
+/// a blank line.
+pub fn document(document: &Document) -> String {
+    let mut out = String::new();
+    if let Some(settings) = &document.settings {
+        write_settings(&mut out, settings);
+    }
+    for (keyword, commits) in &document.keywords {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        if commits.is_empty() {
+            writeln!(out, "{}: []", quote(keyword)).unwrap();
+            continue;
+        }
+        writeln!(out, "{}:", quote(keyword)).unwrap();
+        for (idx, commit) in commits.iter().enumerate() {
+            if idx > 0 {
+                out.push('\n');
+            }
+            write_commit(&mut out, commit);
+        }
+    }
+    if out.is_empty() {
+        out.push_str("{}\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Adjudication, Rating};
+    use std::collections::BTreeMap as Map;
+
+    fn commit(hash: &str, comment: &str) -> Commit {
+        let mut rating = Map::new();
+        rating.insert(
+            "zoë".to_string(),
+            Rating {
+                label: Some("refactoring".to_string()),
+                comment: comment.to_string(),
+            },
+        );
+        Commit {
+            origin: "https://github.com/jwuensche/igitt".to_string(),
+            moved: false,
+            moved_reason: None,
+            commit: hash.to_string(),
+            section: "N/A".to_string(),
+            time: "N/A".to_string(),
+            rating,
+            adjudication: Some(Adjudication {
+                adjudicator: "O'Brien".to_string(),
+                label: "refactoring".to_string(),
+                comment: comment.to_string(),
+            }),
+        }
+    }
+
+    fn round_trip(document: &Document) -> Document {
+        serde_yaml::from_str(&super::document(document)).unwrap()
+    }
+
+    #[test]
+    fn comments_survive_a_round_trip() {
+        let comments = [
+            "",
+            "says \"rename\" but 'moves'",
+            "first line\nsecond line\n\n  indented\n",
+            "Umbenennung – größtenteils 😀, 名前の変更",
+            "key: value # not a comment",
+            "- not a list\\n\ttab",
+            "yes",
+            "~",
+            "0x10",
+            "del \u{7f}, next line \u{85} and other controls \u{80}\u{9f}",
+        ];
+        let mut keywords = Map::new();
+        keywords.insert(
+            "re-name: \"it\"".to_string(),
+            comments
+                .iter()
+                .enumerate()
+                .map(|(idx, comment)| commit(&idx.to_string(), comment))
+                .collect::<Vec<_>>(),
+        );
+        keywords.insert("empty".to_string(), Vec::new());
+        let document = Document {
+            settings: None,
+            keywords,
+        };
+        let parsed = round_trip(&document);
+        assert_eq!(parsed.keywords, document.keywords);
+        assert_eq!(super::document(&parsed), super::document(&document));
+    }
+
+    #[test]
+    fn controls_are_escaped() {
+        assert_eq!(quote("\u{7f}\u{85}\t\u{a0}"), "\"\\x7F\\u0085\\t\u{a0}\"");
+    }
+
+    #[test]
+    fn moved_commit_survives_a_round_trip() {
+        let mut moved = commit("0123abc", "");
+        moved.moved = true;
+        moved.moved_reason = Some("the repository returned \"404 Not Found\"".to_string());
+        moved.rating.clear();
+        moved.adjudication = None;
+        let mut keywords = Map::new();
+        keywords.insert("refactor".to_string(), vec![moved]);
+        let document = Document {
+            settings: None,
+            keywords,
+        };
+        assert_eq!(round_trip(&document).keywords, document.keywords);
+    }
+
+    #[test]
+    fn settings_survive_a_round_trip() {
+        let settings: Settings = serde_yaml::from_str(
+            "labels:\n  - { id: \"a:b\", description: \"Löschen \\\"alt\\\"\", key: \"ä\", positive: true }\n  - { id: other, description: Other, positive: false }\npatterns:\n  \"kw\": [\"re\\\\w+\", \"'quoted'\"]\n",
+        )
+        .unwrap();
+        assert_eq!(settings.labels.iter().count(), 2);
+        let document = Document {
+            settings: Some(settings),
+            keywords: Map::new(),
+        };
+        assert_eq!(round_trip(&document).settings, document.settings);
+    }
+}
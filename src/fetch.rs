@@ -0,0 +1,146 @@
+use crate::cache::Cache;
+use crate::forge::{self, Forges, Quota, Repository};
+use crate::local;
+use anyhow::{anyhow, Context, Result};
+use async_std::task;
+use std::fmt;
+use std::sync::Arc;
+
+/// The commit message and the diff of a commit, errors are shown in place of them.
+pub type Fetched = (Result<String>, Result<String>);
+
+/// Retrieves commits from local repositories, the cache or their forge.
+#[derive(Clone)]
+pub struct Fetcher {
+    forges: Arc<Forges>,
+    cache: Option<Cache>,
+    offline: bool,
+}
+
+/// The commit can no longer be retrieved, because it or its repository has been deleted or moved.
+#[derive(Debug)]
+pub struct Unavailable(pub String);
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "commit is no longer available: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+/// The reason why a commit is no longer available, if that is why it could not be fetched.
+pub fn unavailable(fetched: &Fetched) -> Option<String> {
+    let (message, diff) = fetched;
+    [message, diff]
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .filter_map(|err| err.downcast_ref::<Unavailable>())
+        .map(|unavailable| unavailable.0.clone())
+        .next()
+}
+
+fn both(err: anyhow::Error) -> Fetched {
+    (Err(anyhow!("{:#}", err)), Err(err))
+}
+
+impl Fetcher {
+    pub fn new(forges: Forges, cache: Option<Cache>, offline: bool) -> Self {
+        Fetcher {
+            forges: Arc::new(forges),
+            cache,
+            offline,
+        }
+    }
+
+    pub async fn fetch(&self, origin: &str, commit: &str) -> Fetched {
+        self.fetch_from(origin, commit, true).await
+    }
+
+    /// Fetches the commit without looking into the cache, so that a commit which has been
+    /// cached before is still reported if it is no longer available.
+    pub async fn fetch_uncached(&self, origin: &str, commit: &str) -> Fetched {
+        self.fetch_from(origin, commit, false).await
+    }
+
+    async fn fetch_from(&self, origin: &str, commit: &str, cached: bool) -> Fetched {
+        let (message, diff) = self.retrieve(origin, commit, cached).await;
+        let diff = diff.and_then(|diff| {
+            if diff.trim().is_empty() {
+                Err(Unavailable("the diff is empty".to_string()).into())
+            } else {
+                Ok(diff)
+            }
+        });
+        (message, diff)
+    }
+
+    async fn retrieve(&self, origin: &str, commit: &str, cached: bool) -> Fetched {
+        let mut local_error = None;
+        if let Some(repository) = local::repository_path(origin) {
+            let hash = commit.to_string();
+            match task::spawn_blocking(move || local::read_commit(&repository, &hash)).await {
+                Ok((message, diff)) => return (Ok(message), Ok(diff)),
+                // the repository may be missing on this machine, but part of a prefetched bundle
+                Err(err) => local_error = Some(err),
+            }
+        }
+        if let Some((message, diff)) = self
+            .cache
+            .as_ref()
+            .filter(|_| cached)
+            .and_then(|cache| cache.get(origin, commit))
+        {
+            return (Ok(message), Ok(diff));
+        }
+        if let Some(err) = local_error {
+            return both(err);
+        }
+        if self.offline {
+            return both(anyhow!(
+                "commit {} of {} is not cached, igitt is running offline",
+                commit,
+                origin
+            ));
+        }
+
+        let repository = match Repository::parse(origin) {
+            Ok(repository) => repository,
+            Err(err) => return both(err),
+        };
+        let forge = match self.forges.get(&repository) {
+            Ok(forge) => forge,
+            Err(err) => return both(err),
+        };
+        let (message, diff) = forge::fetch(
+            forge,
+            repository,
+            commit.to_string(),
+            &self.forges.rate_limits,
+        )
+        .await;
+        if let (Ok(message), Ok(diff), Some(cache)) = (&message, &diff, &self.cache) {
+            // the cache is only an optimization, failing to fill it is not worth interrupting
+            cache.put(origin, commit, message, diff).ok();
+        }
+        (message, diff)
+    }
+
+    /// The last known API quota of the forge hosting the origin.
+    pub fn quota(&self, origin: &str) -> Option<Quota> {
+        let repository = Repository::parse(origin).ok()?;
+        self.forges.rate_limits.get(&repository.host)
+    }
+
+    /// Fetches a commit and makes sure it is stored in the cache, so that it is available offline.
+    pub async fn store(&self, origin: &str, commit: &str) -> Result<()> {
+        let cache = self
+            .cache
+            .as_ref()
+            .context("no cache directory available")?;
+        let (message, diff) = self.fetch(origin, commit).await;
+        let (message, diff) = (message?, diff?);
+        // stored again, as fetching ignores failures to fill the cache and skips local commits
+        cache.put(origin, commit, &message, &diff)
+    }
+}
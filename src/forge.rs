@@ -0,0 +1,715 @@
+use crate::config::{ForgeConfig, ForgeKind};
+use crate::fetch::Unavailable;
+use anyhow::{anyhow, bail, Context, Result};
+use async_std::prelude::*;
+use async_std::task;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap as Map;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often a request is repeated after hitting the rate limit.
+const RATE_LIMIT_RETRIES: u32 = 5;
+/// The longest time waited for the rate limit to reset before a request is repeated.
+const MAX_BACKOFF: u64 = 60;
+
+/// A repository hosted on a forge, parsed from the `origin` of a commit.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Scheme and host, e.g. `https://github.com`.
+    pub base: String,
+    pub host: String,
+    /// Path of the repository on the host without a trailing `.git`.
+    pub path: String,
+}
+
+impl Repository {
+    /// Parses an http(s) url or an scp-like origin as `git@github.com:owner/repo.git`, whose
+    /// forge is accessed via https.
+    pub fn parse(origin: &str) -> Result<Repository> {
+        let scp_re = Regex::new(r"^(?:[^@/:]+@)?([^@/:]+):/?(.+?)(?:\.git)?/?$")?;
+        if let Some(captures) = scp_re.captures(origin).filter(|_| !origin.contains("://")) {
+            let host = &captures[1];
+            return Ok(Repository {
+                base: format!("https://{}", host),
+                host: host.to_string(),
+                path: captures[2].to_string(),
+            });
+        }
+        let url_re = Regex::new(r"^(https?)://(.+?)/(.+?)(?:\.git)?/?$")?;
+        let captures = url_re.captures(origin).context("could not parse origin")?;
+        let scheme = captures
+            .get(1)
+            .context("no valid scheme for origin")?
+            .as_str();
+        let host = captures
+            .get(2)
+            .context("no valid domain for origin")?
+            .as_str();
+        let path = captures
+            .get(3)
+            .context("no valid path for origin")?
+            .as_str();
+        Ok(Repository {
+            base: format!("{}://{}", scheme, host),
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    fn encoded_path(&self) -> String {
+        utf8_percent_encode(&self.path, NON_ALPHANUMERIC).to_string()
+    }
+}
+
+pub struct Request {
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+}
+
+impl Request {
+    fn new(url: String) -> Self {
+        Request {
+            url,
+            headers: Vec::new(),
+        }
+    }
+
+    fn header(mut self, key: &'static str, value: Option<String>) -> Self {
+        if let Some(value) = value {
+            self.headers.push((key, value));
+        }
+        self
+    }
+
+    fn to_surf(&self) -> surf::Request<impl surf::middleware::HttpClient> {
+        self.headers
+            .iter()
+            .fold(surf::get(&self.url), |request, (key, value)| {
+                request.set_header(key, value)
+            })
+    }
+}
+
+/// The API requests left until the rate limit of a host is reached.
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix time at which the quota is reset.
+    pub reset: Option<u64>,
+}
+
+impl Quota {
+    /// GitHub sends `X-RateLimit-*` headers, GitLab `RateLimit-*` headers.
+    fn from_response(response: &surf::Response) -> Option<Quota> {
+        let header = |name: &'static str| -> Option<u64> {
+            response
+                .header(name)
+                .or_else(|| response.header(&name[2..]))
+                .and_then(|value| value.trim().parse().ok())
+        };
+        Some(Quota {
+            limit: header("X-RateLimit-Limit")?,
+            remaining: header("X-RateLimit-Remaining")?,
+            reset: header("X-RateLimit-Reset"),
+        })
+    }
+
+    /// Less than a tenth of the quota is left.
+    pub fn is_low(&self) -> bool {
+        self.remaining * 10 < self.limit
+    }
+}
+
+impl fmt::Display for Quota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.remaining, self.limit)
+    }
+}
+
+/// The last known quota of every host.
+#[derive(Default)]
+pub struct RateLimits {
+    quotas: Mutex<Map<String, Quota>>,
+}
+
+impl RateLimits {
+    pub fn get(&self, host: &str) -> Option<Quota> {
+        self.quotas.lock().unwrap().get(host).copied()
+    }
+
+    fn update(&self, host: &str, quota: Quota) {
+        self.quotas.lock().unwrap().insert(host.to_string(), quota);
+    }
+}
+
+/// A forge hosting git repositories, which is able to serve commit messages and diffs.
+pub trait Forge: Send + Sync {
+    /// The request answering with a JSON description of the commit.
+    fn message_request(&self, repository: &Repository, commit: &str) -> Request;
+    /// The request answering with the unified diff of the commit.
+    fn diff_request(&self, repository: &Repository, commit: &str) -> Request;
+    /// Extracts the commit message from the answer to the message request.
+    fn message(&self, response: &Value) -> Option<String>;
+}
+
+pub struct GitHub {
+    api: String,
+    token: Option<String>,
+}
+
+impl Forge for GitHub {
+    fn message_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/repos/{}/git/commits/{}",
+            self.api, repository.path, commit
+        ))
+        .header(
+            "Authorization",
+            self.token.as_ref().map(|t| format!("token {}", t)),
+        )
+    }
+
+    fn diff_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/repos/{}/commits/{}",
+            self.api, repository.path, commit
+        ))
+        .header("Accept", Some("application/vnd.github.v3.diff".to_string()))
+        .header(
+            "Authorization",
+            self.token.as_ref().map(|t| format!("token {}", t)),
+        )
+    }
+
+    fn message(&self, response: &Value) -> Option<String> {
+        response["message"].as_str().map(str::to_string)
+    }
+}
+
+pub struct GitLab {
+    api: String,
+    token: Option<String>,
+}
+
+impl Forge for GitLab {
+    fn message_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/projects/{}/repository/commits/{}",
+            self.api,
+            repository.encoded_path(),
+            commit
+        ))
+        .header("PRIVATE-TOKEN", self.token.clone())
+    }
+
+    fn diff_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/{}/-/commit/{}.diff",
+            repository.base, repository.path, commit
+        ))
+        .header("PRIVATE-TOKEN", self.token.clone())
+    }
+
+    fn message(&self, response: &Value) -> Option<String> {
+        response["message"].as_str().map(str::to_string)
+    }
+}
+
+/// Gitea and its fork Forgejo.
+pub struct Gitea {
+    api: String,
+    token: Option<String>,
+}
+
+impl Forge for Gitea {
+    fn message_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/repos/{}/git/commits/{}",
+            self.api, repository.path, commit
+        ))
+        .header(
+            "Authorization",
+            self.token.as_ref().map(|t| format!("token {}", t)),
+        )
+    }
+
+    fn diff_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/repos/{}/git/commits/{}.diff",
+            self.api, repository.path, commit
+        ))
+        .header(
+            "Authorization",
+            self.token.as_ref().map(|t| format!("token {}", t)),
+        )
+    }
+
+    fn message(&self, response: &Value) -> Option<String> {
+        response["commit"]["message"].as_str().map(str::to_string)
+    }
+}
+
+pub struct Bitbucket {
+    api: String,
+    token: Option<String>,
+}
+
+impl Forge for Bitbucket {
+    fn message_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/repositories/{}/commit/{}",
+            self.api, repository.path, commit
+        ))
+        .header(
+            "Authorization",
+            self.token.as_ref().map(|t| format!("Bearer {}", t)),
+        )
+    }
+
+    fn diff_request(&self, repository: &Repository, commit: &str) -> Request {
+        Request::new(format!(
+            "{}/repositories/{}/diff/{}",
+            self.api, repository.path, commit
+        ))
+        .header(
+            "Authorization",
+            self.token.as_ref().map(|t| format!("Bearer {}", t)),
+        )
+    }
+
+    fn message(&self, response: &Value) -> Option<String> {
+        response["message"].as_str().map(str::to_string)
+    }
+}
+
+fn missing_token(forge: &str, host: &str) -> String {
+    format!(
+        "no {forge} token for {host}, pass it with --{flag}, set IGITT_{env}_TOKEN or add it to the config file",
+        forge = forge,
+        host = host,
+        flag = forge.to_lowercase(),
+        env = forge.to_uppercase(),
+    )
+}
+
+/// Selects the forge backend of a repository by its host.
+pub struct Forges {
+    hosts: Map<String, ForgeConfig>,
+    github_token: Option<String>,
+    gitlab_token: Option<String>,
+    pub rate_limits: RateLimits,
+}
+
+impl Forges {
+    pub fn new(
+        configured: &Map<String, ForgeConfig>,
+        github_token: Option<String>,
+        gitlab_token: Option<String>,
+    ) -> Self {
+        let mut hosts = Map::new();
+        for (host, kind) in &[
+            ("github.com", ForgeKind::GitHub),
+            ("gitlab.com", ForgeKind::GitLab),
+            ("codeberg.org", ForgeKind::Gitea),
+            ("bitbucket.org", ForgeKind::Bitbucket),
+        ] {
+            hosts.insert(
+                host.to_string(),
+                ForgeConfig {
+                    kind: *kind,
+                    api: None,
+                    token: None,
+                },
+            );
+        }
+        hosts.extend(configured.clone());
+        Forges {
+            hosts,
+            github_token,
+            gitlab_token,
+            rate_limits: RateLimits::default(),
+        }
+    }
+
+    /// Whether the host is a known forge or has been declared in the config.
+    pub fn supports(&self, host: &str) -> bool {
+        self.hosts.contains_key(host)
+    }
+
+    pub fn get(&self, repository: &Repository) -> Result<Arc<dyn Forge>> {
+        let config = self
+            .hosts
+            .get(&repository.host)
+            .with_context(|| format!("invalid domain {}", repository.host))?;
+        let api = config.api.clone();
+        let base = &repository.base;
+        let forge: Arc<dyn Forge> = match config.kind {
+            ForgeKind::GitHub => Arc::new(GitHub {
+                api: api.unwrap_or_else(|| {
+                    if repository.host == "github.com" {
+                        "https://api.github.com".to_string()
+                    } else {
+                        format!("{}/api/v3", base)
+                    }
+                }),
+                token: Some(
+                    config
+                        .token
+                        .clone()
+                        .or_else(|| self.github_token.clone())
+                        .with_context(|| missing_token("GitHub", &repository.host))?,
+                ),
+            }),
+            ForgeKind::GitLab => Arc::new(GitLab {
+                api: api.unwrap_or_else(|| format!("{}/api/v4", base)),
+                token: Some(
+                    config
+                        .token
+                        .clone()
+                        .or_else(|| self.gitlab_token.clone())
+                        .with_context(|| missing_token("GitLab", &repository.host))?,
+                ),
+            }),
+            ForgeKind::Gitea => Arc::new(Gitea {
+                api: api.unwrap_or_else(|| format!("{}/api/v1", base)),
+                token: config.token.clone(),
+            }),
+            ForgeKind::Bitbucket => Arc::new(Bitbucket {
+                api: api.unwrap_or_else(|| {
+                    if repository.host == "bitbucket.org" {
+                        "https://api.bitbucket.org/2.0".to_string()
+                    } else {
+                        format!("{}/2.0", base)
+                    }
+                }),
+                token: config.token.clone(),
+            }),
+        };
+        Ok(forge)
+    }
+}
+
+/// How long to wait before repeating a request which hit the rate limit.
+fn backoff(response: &surf::Response, quota: Option<Quota>, attempt: u32) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    let retry_after = response
+        .header("Retry-After")
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .or_else(|| quota?.reset.map(|reset| reset.saturating_sub(now)))
+        .unwrap_or(0);
+    Duration::from_secs(retry_after.max(1 << attempt).min(MAX_BACKOFF))
+}
+
+async fn send(request: Request, host: &str, rate_limits: &RateLimits) -> Result<String> {
+    let mut attempt = 0;
+    let mut response = loop {
+        let response = request.to_surf().await.map_err(|err| anyhow!("{}", err))?;
+        let quota = Quota::from_response(&response);
+        if let Some(quota) = quota {
+            rate_limits.update(host, quota);
+        }
+        let status = response.status().as_u16();
+        let limited =
+            status == 429 || (status == 403 && matches!(quota, Some(q) if q.remaining == 0));
+        if !limited {
+            break response;
+        }
+        if attempt == RATE_LIMIT_RETRIES {
+            bail!("the rate limit of {} has been exceeded", host);
+        }
+        task::sleep(backoff(&response, quota, attempt)).await;
+        attempt += 1;
+    };
+    let status = response.status();
+    if status.is_redirection() {
+        // forges redirect requests for renamed or transferred repositories
+        let reason = match response.header("Location") {
+            Some(location) => format!("the repository has moved to {}", location),
+            None => format!("the repository has moved ({})", status),
+        };
+        bail!(Unavailable(reason));
+    }
+    if status.as_u16() == 404 || status.as_u16() == 410 {
+        bail!(Unavailable(status.to_string()));
+    }
+    let body = response
+        .body_string()
+        .await
+        .map_err(|err| anyhow!("{}", err))?;
+    if !status.is_success() {
+        bail!("{}: {}", status, body);
+    }
+    Ok(body)
+}
+
+/// Fetches message and diff of a commit concurrently.
+pub async fn fetch(
+    forge: Arc<dyn Forge>,
+    repository: Repository,
+    commit: String,
+    rate_limits: &RateLimits,
+) -> (Result<String>, Result<String>) {
+    let host = &repository.host;
+    let message_request = send(
+        forge.message_request(&repository, &commit),
+        host,
+        rate_limits,
+    );
+    let diff_request = send(forge.diff_request(&repository, &commit), host, rate_limits);
+    let (message_result, diff) = message_request.join(diff_request).await;
+    let message = message_result.and_then(|body| {
+        let response: Value = serde_json::from_str(&body)?;
+        forge
+            .message(&response)
+            .context("!! Commit message not available !!")
+    });
+    (message, diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+    const DIFF: &str = "diff --git a/file b/file\n";
+
+    /// A request received by the mock server, header names are lowercase.
+    #[derive(Debug, Clone)]
+    struct Received {
+        path: String,
+        headers: Map<String, String>,
+    }
+
+    /// An HTTP server answering requests of the given paths with their body and all other
+    /// requests with 404.
+    struct MockServer {
+        base: String,
+        received: Arc<Mutex<Vec<Received>>>,
+    }
+
+    impl MockServer {
+        fn start(routes: Vec<(String, String)>) -> MockServer {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let base = format!("http://{}", listener.local_addr().unwrap());
+            let received = Arc::new(Mutex::new(Vec::new()));
+            let server_received = received.clone();
+            let routes = Arc::new(routes);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let received = server_received.clone();
+                    let routes = routes.clone();
+                    thread::spawn(move || serve(stream.unwrap(), &routes, &received));
+                }
+            });
+            MockServer { base, received }
+        }
+
+        fn request(&self, path: &str) -> Received {
+            self.received
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|received| received.path == path)
+                .cloned()
+                .unwrap_or_else(|| panic!("no request of {}", path))
+        }
+    }
+
+    fn serve(stream: TcpStream, routes: &[(String, String)], received: &Mutex<Vec<Received>>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut stream = stream;
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let path = request_line
+            .split_whitespace()
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+        let mut headers = Map::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.to_lowercase(), value.trim().to_string());
+            }
+        }
+        received.lock().unwrap().push(Received {
+            path: path.clone(),
+            headers,
+        });
+        let (status, body) = match routes.iter().find(|(route, _)| *route == path) {
+            Some((_, body)) => ("200 OK", body.as_str()),
+            None => ("404 Not Found", ""),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+        .unwrap();
+    }
+
+    /// Fetches the commit from a repository on the mock server, which runs the given forge.
+    fn fetch_from(server: &MockServer, kind: ForgeKind) -> (Result<String>, Result<String>) {
+        let repository = Repository::parse(&format!("{}/owner/repo.git", server.base)).unwrap();
+        let mut configured = Map::new();
+        configured.insert(
+            repository.host.clone(),
+            ForgeConfig {
+                kind,
+                api: Some(format!("{}/api", server.base)),
+                token: Some("secret".to_string()),
+            },
+        );
+        let forges = Forges::new(&configured, None, None);
+        let forge = forges.get(&repository).unwrap();
+        task::block_on(fetch(
+            forge,
+            repository,
+            COMMIT.to_string(),
+            &forges.rate_limits,
+        ))
+    }
+
+    fn route(path: String, body: &str) -> (String, String) {
+        (path, body.to_string())
+    }
+
+    #[test]
+    fn scp_like_origin() {
+        let repository = Repository::parse("git@github.com:owner/repo.git").unwrap();
+        assert_eq!(repository.base, "https://github.com");
+        assert_eq!(repository.host, "github.com");
+        assert_eq!(repository.path, "owner/repo");
+        let repository = Repository::parse("gitlab.com:group/sub/repo").unwrap();
+        assert_eq!(repository.base, "https://gitlab.com");
+        assert_eq!(repository.path, "group/sub/repo");
+    }
+
+    #[test]
+    fn github() {
+        let message_path = format!("/api/repos/owner/repo/git/commits/{}", COMMIT);
+        let diff_path = format!("/api/repos/owner/repo/commits/{}", COMMIT);
+        let server = MockServer::start(vec![
+            route(message_path.clone(), r#"{"message": "Extract method"}"#),
+            route(diff_path.clone(), DIFF),
+        ]);
+        let (message, diff) = fetch_from(&server, ForgeKind::GitHub);
+        assert_eq!(message.unwrap(), "Extract method");
+        assert_eq!(diff.unwrap(), DIFF);
+        let message_request = server.request(&message_path);
+        assert_eq!(message_request.headers["authorization"], "token secret");
+        let diff_request = server.request(&diff_path);
+        assert_eq!(diff_request.headers["authorization"], "token secret");
+        assert_eq!(
+            diff_request.headers["accept"],
+            "application/vnd.github.v3.diff"
+        );
+    }
+
+    #[test]
+    fn gitlab() {
+        let message_path = format!("/api/projects/owner%2Frepo/repository/commits/{}", COMMIT);
+        // diffs are served by the web interface instead of the API
+        let diff_path = format!("/owner/repo/-/commit/{}.diff", COMMIT);
+        let server = MockServer::start(vec![
+            route(message_path.clone(), r#"{"message": "Extract method"}"#),
+            route(diff_path.clone(), DIFF),
+        ]);
+        let (message, diff) = fetch_from(&server, ForgeKind::GitLab);
+        assert_eq!(message.unwrap(), "Extract method");
+        assert_eq!(diff.unwrap(), DIFF);
+        assert_eq!(
+            server.request(&message_path).headers["private-token"],
+            "secret"
+        );
+        assert_eq!(
+            server.request(&diff_path).headers["private-token"],
+            "secret"
+        );
+    }
+
+    #[test]
+    fn gitea() {
+        let message_path = format!("/api/repos/owner/repo/git/commits/{}", COMMIT);
+        let diff_path = format!("/api/repos/owner/repo/git/commits/{}.diff", COMMIT);
+        let server = MockServer::start(vec![
+            route(
+                message_path.clone(),
+                r#"{"commit": {"message": "Extract method"}}"#,
+            ),
+            route(diff_path.clone(), DIFF),
+        ]);
+        let (message, diff) = fetch_from(&server, ForgeKind::Gitea);
+        assert_eq!(message.unwrap(), "Extract method");
+        assert_eq!(diff.unwrap(), DIFF);
+        assert_eq!(
+            server.request(&message_path).headers["authorization"],
+            "token secret"
+        );
+        assert_eq!(
+            server.request(&diff_path).headers["authorization"],
+            "token secret"
+        );
+    }
+
+    #[test]
+    fn bitbucket() {
+        let message_path = format!("/api/repositories/owner/repo/commit/{}", COMMIT);
+        let diff_path = format!("/api/repositories/owner/repo/diff/{}", COMMIT);
+        let server = MockServer::start(vec![
+            route(message_path.clone(), r#"{"message": "Extract method"}"#),
+            route(diff_path.clone(), DIFF),
+        ]);
+        let (message, diff) = fetch_from(&server, ForgeKind::Bitbucket);
+        assert_eq!(message.unwrap(), "Extract method");
+        assert_eq!(diff.unwrap(), DIFF);
+        assert_eq!(
+            server.request(&message_path).headers["authorization"],
+            "Bearer secret"
+        );
+        assert_eq!(
+            server.request(&diff_path).headers["authorization"],
+            "Bearer secret"
+        );
+    }
+
+    #[test]
+    fn missing_commit_is_unavailable() {
+        let server = MockServer::start(Vec::new());
+        let (message, diff) = fetch_from(&server, ForgeKind::GitHub);
+        for result in [message, diff].iter() {
+            let err = result.as_ref().unwrap_err();
+            assert!(err.downcast_ref::<Unavailable>().is_some(), "{:#}", err);
+        }
+    }
+
+    #[test]
+    fn unparseable_message() {
+        let message_path = format!("/api/repos/owner/repo/git/commits/{}", COMMIT);
+        let diff_path = format!("/api/repos/owner/repo/commits/{}", COMMIT);
+        let server = MockServer::start(vec![
+            route(message_path, r#"{"sha": "0123"}"#),
+            route(diff_path, DIFF),
+        ]);
+        let (message, diff) = fetch_from(&server, ForgeKind::GitHub);
+        assert!(message.is_err());
+        assert_eq!(diff.unwrap(), DIFF);
+    }
+}
@@ -0,0 +1,58 @@
+use cursive::theme::Style;
+use cursive::utils::span::SpannedString;
+use regex::Regex;
+use std::ops::Range;
+
+/// Byte ranges of all matches of the patterns in the text, sorted and with overlaps merged.
+pub fn find(text: &str, patterns: &[Regex]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = patterns
+        .iter()
+        .flat_map(|pattern| pattern.find_iter(text))
+        .filter(|found| !found.range().is_empty())
+        .map(|found| found.range())
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Applies a style on top of the given byte ranges of a styled text.
+pub fn emphasize(
+    text: &SpannedString<Style>,
+    ranges: &[Range<usize>],
+    style: Style,
+) -> SpannedString<Style> {
+    let mut result = SpannedString::new();
+    let mut ranges = ranges.iter().peekable();
+    let mut offset = 0;
+    for span in text.spans() {
+        let end = offset + span.content.len();
+        let mut pos = offset;
+        while pos < end {
+            // ranges ending before this position are done
+            while matches!(ranges.peek(), Some(range) if range.end <= pos) {
+                ranges.next();
+            }
+            let (until, emphasized) = match ranges.peek() {
+                Some(range) if range.start <= pos => (range.end.min(end), true),
+                Some(range) => (range.start.min(end), false),
+                None => (end, false),
+            };
+            let part = &span.content[pos - offset..until - offset];
+            if emphasized {
+                result.append_styled(part, span.attr.combine(style));
+            } else {
+                result.append_styled(part, *span.attr);
+            }
+            pos = until;
+        }
+        offset = end;
+    }
+    result
+}
@@ -0,0 +1,51 @@
+/// A change of the commit at a position of the walk, along with its state before and after.
+pub struct Edit<T> {
+    pub pos: usize,
+    pub before: T,
+    pub after: T,
+}
+
+/// The changes of a session, which can be undone and redone again.
+pub struct History<T> {
+    done: Vec<Edit<T>>,
+    undone: Vec<Edit<T>>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        History {
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+}
+
+impl<T> History<T> {
+    /// Records a change, the changes undone before can't be redone anymore.
+    pub fn record(&mut self, edit: Edit<T>) {
+        self.done.push(edit);
+        self.undone.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Returns the last change, whose `before` has to be restored.
+    pub fn undo(&mut self) -> Option<&Edit<T>> {
+        let edit = self.done.pop()?;
+        self.undone.push(edit);
+        self.undone.last()
+    }
+
+    /// Returns the last undone change, whose `after` has to be restored.
+    pub fn redo(&mut self) -> Option<&Edit<T>> {
+        let edit = self.undone.pop()?;
+        self.done.push(edit);
+        self.done.last()
+    }
+}
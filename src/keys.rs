@@ -0,0 +1,304 @@
+use crate::labels::LabelSchema;
+use anyhow::{bail, Result};
+use cursive::align::HAlign;
+use cursive::theme::{PaletteColor, Style};
+use cursive::utils::span::SpannedString;
+use cursive::view::Scrollable;
+use cursive::views::{Dialog, TextView};
+use cursive::Cursive;
+use serde::Deserialize;
+use std::collections::BTreeMap as Map;
+
+/// Actions of the main view which can be bound to a key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    Save,
+    Prev,
+    Next,
+    NextUnrated,
+    Overview,
+    Search,
+    NextMatch,
+    PrevMatch,
+    ToggleSideBySide,
+    Collapse,
+    Unavailable,
+    Undo,
+    Redo,
+    Help,
+}
+
+impl Action {
+    /// Contextual actions only take effect while a search is active, otherwise their key is
+    /// left to the label bound to it.
+    pub fn is_contextual(self) -> bool {
+        matches!(self, Action::NextMatch | Action::PrevMatch)
+    }
+
+    /// The name of the action in the config file.
+    fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Save => "save",
+            Action::Prev => "prev",
+            Action::Next => "next",
+            Action::NextUnrated => "next_unrated",
+            Action::Overview => "overview",
+            Action::Search => "search",
+            Action::NextMatch => "next_match",
+            Action::PrevMatch => "prev_match",
+            Action::ToggleSideBySide => "toggle_side_by_side",
+            Action::Collapse => "collapse",
+            Action::Unavailable => "unavailable",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Help => "help",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Save => "Save",
+            Action::Prev => "Go to prev commit",
+            Action::Next => "Go to next commit",
+            Action::NextUnrated => "Go to next unrated commit",
+            Action::Overview => "Overview of all commits",
+            Action::Search => "Search message and diff",
+            Action::NextMatch => "Go to next match",
+            Action::PrevMatch => "Go to prev match",
+            Action::ToggleSideBySide => "Toggle side-by-side diff",
+            Action::Collapse => "Collapse or expand file",
+            Action::Unavailable => "This commit is invalid",
+            Action::Undo => "Undo the last rating change",
+            Action::Redo => "Redo the last undone change",
+            Action::Help => "Show the keybindings",
+        }
+    }
+}
+
+/// The keys of the main view as configured in the `keys` section of the config file.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keys {
+    pub quit: char,
+    pub save: char,
+    pub prev: char,
+    pub next: char,
+    pub next_unrated: char,
+    pub overview: char,
+    pub search: char,
+    pub next_match: char,
+    pub prev_match: char,
+    pub toggle_side_by_side: char,
+    pub collapse: char,
+    pub unavailable: char,
+    pub undo: char,
+    pub redo: char,
+    pub help: char,
+    /// Keys of labels, overriding the keys declared in the document.
+    pub labels: Map<String, char>,
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        Keys {
+            quit: 'q',
+            save: 's',
+            prev: ',',
+            next: '.',
+            next_unrated: 'u',
+            overview: 'o',
+            search: '/',
+            next_match: 'n',
+            prev_match: 'N',
+            toggle_side_by_side: 't',
+            collapse: 'c',
+            unavailable: 'i',
+            undo: 'z',
+            redo: 'Z',
+            help: '?',
+            labels: Map::new(),
+        }
+    }
+}
+
+impl Keys {
+    fn actions(&self) -> Vec<(char, Action)> {
+        vec![
+            (self.quit, Action::Quit),
+            (self.save, Action::Save),
+            (self.prev, Action::Prev),
+            (self.next, Action::Next),
+            (self.next_unrated, Action::NextUnrated),
+            (self.overview, Action::Overview),
+            (self.search, Action::Search),
+            (self.next_match, Action::NextMatch),
+            (self.prev_match, Action::PrevMatch),
+            (self.toggle_side_by_side, Action::ToggleSideBySide),
+            (self.collapse, Action::Collapse),
+            (self.unavailable, Action::Unavailable),
+            (self.undo, Action::Undo),
+            (self.redo, Action::Redo),
+            (self.help, Action::Help),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binding {
+    Action(Action),
+    /// Selects the label with the given id.
+    Label(String),
+}
+
+impl Binding {
+    fn is_contextual(&self) -> bool {
+        matches!(self, Binding::Action(action) if action.is_contextual())
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Binding::Action(action) => format!("action '{}'", action.name()),
+            Binding::Label(id) => format!("label '{}'", id),
+        }
+    }
+}
+
+/// The active keybindings, grouped by key.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    keys: Map<char, Vec<Binding>>,
+}
+
+impl Bindings {
+    /// Binds the actions and labels to their keys, a key may only be shared by a contextual
+    /// action and a label.
+    pub fn new(keys: &Keys, labels: &LabelSchema) -> Result<Bindings> {
+        for id in keys.labels.keys() {
+            if labels.get(id).is_none() {
+                bail!("there is a key for the unknown label '{}'", id);
+            }
+        }
+        let label_keys = labels.iter().filter_map(|label| {
+            keys.labels
+                .get(&label.id)
+                .copied()
+                .or(label.key)
+                .map(|key| (key, Binding::Label(label.id.clone())))
+        });
+        let mut bound: Map<char, Vec<Binding>> = Map::new();
+        for (key, binding) in keys
+            .actions()
+            .into_iter()
+            .map(|(key, action)| (key, Binding::Action(action)))
+            .chain(label_keys)
+        {
+            bound.entry(key).or_default().push(binding);
+        }
+        for (key, bindings) in bound.iter_mut() {
+            // the contextual action gets the first chance to handle the key
+            bindings.sort_by_key(|binding| !binding.is_contextual());
+            let (contextual, others): (Vec<_>, Vec<_>) =
+                bindings.iter().partition(|binding| binding.is_contextual());
+            let conflict = match (contextual.as_slice(), others.as_slice()) {
+                ([first, second, ..], _) | (_, [first, second, ..]) => Some((first, second)),
+                ([contextual], [action @ Binding::Action(_)]) => Some((contextual, action)),
+                _ => None,
+            };
+            if let Some((first, second)) = conflict {
+                bail!(
+                    "key '{}' is bound to both {} and {}",
+                    key,
+                    first.describe(),
+                    second.describe()
+                );
+            }
+        }
+        Ok(Bindings { keys: bound })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (char, &[Binding])> {
+        self.keys
+            .iter()
+            .map(|(key, bindings)| (*key, bindings.as_slice()))
+    }
+}
+
+/// Shows an overlay listing all active keybindings.
+pub fn show_help(siv: &mut Cursive, bindings: &Bindings, labels: &LabelSchema) {
+    let mut content = SpannedString::<Style>::new();
+    for (key, bindings) in bindings.iter() {
+        for binding in bindings {
+            let description = match binding {
+                Binding::Action(action) if action.is_contextual() => {
+                    format!("{} (while searching)", action.description())
+                }
+                Binding::Action(action) => action.description().to_string(),
+                Binding::Label(id) => labels
+                    .get(id)
+                    .map_or(id.clone(), |label| label.description.clone()),
+            };
+            content.append_styled(format!("{:>5}  ", key), PaletteColor::Secondary);
+            content.append_plain(format!("{}\n", description));
+        }
+    }
+    content.append_styled(format!("{:>5}  ", "Esc"), PaletteColor::Secondary);
+    content.append_plain("Clear search");
+    siv.add_layer(
+        Dialog::around(TextView::new(content).scrollable())
+            .title("Keybindings")
+            .title_position(HAlign::Left)
+            .button("Ok", |siv| {
+                siv.pop_layer();
+            }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(keys: &Keys) -> String {
+        Bindings::new(keys, &LabelSchema::default())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn actions_may_not_share_a_key() {
+        let keys = Keys {
+            save: 'q',
+            ..Keys::default()
+        };
+        assert_eq!(
+            error(&keys),
+            "key 'q' is bound to both action 'quit' and action 'save'"
+        );
+    }
+
+    #[test]
+    fn label_may_share_the_key_of_a_contextual_action() {
+        // the default key of 'no_refactoring' is the one of next_match
+        let bindings = Bindings::new(&Keys::default(), &LabelSchema::default()).unwrap();
+        let (_, shared) = bindings.iter().find(|(key, _)| *key == 'n').unwrap();
+        assert_eq!(
+            shared,
+            &[
+                Binding::Action(Action::NextMatch),
+                Binding::Label("no_refactoring".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn label_may_not_share_the_key_of_another_action() {
+        let mut keys = Keys::default();
+        keys.labels.insert("refactoring".to_string(), 'q');
+        assert_eq!(
+            error(&keys),
+            "key 'q' is bound to both action 'quit' and label 'refactoring'"
+        );
+    }
+}
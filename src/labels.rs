@@ -0,0 +1,90 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Label used for ratings which have been stored with `is_refactoring: true`.
+pub const REFACTORING: &str = "refactoring";
+/// Label used for ratings which have been stored with `is_refactoring: false`.
+pub const NO_REFACTORING: &str = "no_refactoring";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Label {
+    pub id: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<char>,
+    /// Commits carrying this label count as true positives for their keyword.
+    #[serde(default)]
+    pub positive: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct LabelSchema {
+    labels: Vec<Label>,
+}
+
+impl Default for LabelSchema {
+    fn default() -> Self {
+        LabelSchema {
+            labels: vec![
+                Label {
+                    id: REFACTORING.to_string(),
+                    description: "This commit is a valid refactoring".to_string(),
+                    key: Some('y'),
+                    positive: true,
+                },
+                Label {
+                    id: NO_REFACTORING.to_string(),
+                    description: "This commit does not contain refactoring".to_string(),
+                    key: Some('n'),
+                    positive: false,
+                },
+            ],
+        }
+    }
+}
+
+impl LabelSchema {
+    pub fn iter(&self) -> impl Iterator<Item = &Label> {
+        self.labels.iter()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Label> {
+        self.labels.iter().find(|label| label.id == id)
+    }
+
+    pub fn is_positive(&self, id: &str) -> bool {
+        matches!(self.get(id), Some(label) if label.positive)
+    }
+
+    /// The label preselected for commits which have not been rated yet.
+    pub fn default_label(&self) -> &Label {
+        self.labels
+            .iter()
+            .find(|label| !label.positive)
+            .unwrap_or(&self.labels[0])
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.labels.is_empty() {
+            bail!("the label schema has to contain at least one label");
+        }
+        let mut ids = HashSet::new();
+        let mut keys = HashSet::new();
+        for label in &self.labels {
+            if label.id.is_empty() {
+                bail!("label ids must not be empty");
+            }
+            if !ids.insert(&label.id) {
+                bail!("label '{}' is declared more than once", label.id);
+            }
+            if let Some(key) = label.key {
+                if !keys.insert(key) {
+                    bail!("key '{}' is assigned to more than one label", key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use crate::fetch::Unavailable;
+use anyhow::{anyhow, Context, Result};
+use git2::{DiffFormat, ErrorCode, Repository};
+use std::path::{Path, PathBuf};
+
+/// Returns the path of the repository if the origin refers to a repository on disk, either as
+/// `file://` url or as plain path.
+pub fn repository_path(origin: &str) -> Option<PathBuf> {
+    if let Some(path) = origin.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+    if origin.contains("://") || is_scp_like(origin) {
+        return None;
+    }
+    Some(PathBuf::from(origin))
+}
+
+/// Whether the origin is written like `git@github.com:owner/repo`, as git does it is told apart
+/// from a path by a colon before the first slash.
+fn is_scp_like(origin: &str) -> bool {
+    match (origin.find(':'), origin.find('/')) {
+        (Some(colon), Some(slash)) => colon < slash,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Reads the message and the diff to the first parent of a commit, the diff is formatted like
+/// the `.diff` files served by the forges.
+pub fn read_commit(path: &Path, hash: &str) -> Result<(String, String)> {
+    let repository = Repository::open(path)
+        .with_context(|| format!("could not open repository {}", path.display()))?;
+    let commit = repository
+        .revparse_single(hash)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|err| match err.code() {
+            ErrorCode::NotFound => anyhow!(Unavailable(format!(
+                "{} not found in {}",
+                hash,
+                path.display()
+            ))),
+            _ => anyhow!(err).context(format!("could not find commit {}", hash)),
+        })?;
+    let message = String::from_utf8_lossy(commit.message_bytes()).to_string();
+
+    let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    let diff = repository.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if let '+' | '-' | ' ' = line.origin() {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+
+    Ok((message, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_origins() {
+        assert_eq!(
+            repository_path("file:///srv/git/repo"),
+            Some(PathBuf::from("/srv/git/repo"))
+        );
+        assert_eq!(
+            repository_path("/srv/git/repo"),
+            Some(PathBuf::from("/srv/git/repo"))
+        );
+        assert_eq!(repository_path("../repo"), Some(PathBuf::from("../repo")));
+        // a colon after the first slash is part of the path
+        assert_eq!(
+            repository_path("./backups/12:00/repo"),
+            Some(PathBuf::from("./backups/12:00/repo"))
+        );
+    }
+
+    #[test]
+    fn remote_origins() {
+        assert_eq!(repository_path("https://github.com/owner/repo"), None);
+        assert_eq!(repository_path("git@github.com:owner/repo.git"), None);
+        assert_eq!(repository_path("gitlab.com:group/repo"), None);
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// The session holding the lock on a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holder {
+    pub pid: u32,
+    pub host: String,
+}
+
+impl Holder {
+    fn current() -> Holder {
+        Holder {
+            pid: std::process::id(),
+            host: hostname(),
+        }
+    }
+
+    fn parse(content: &str) -> Option<Holder> {
+        let mut parts = content.split_whitespace();
+        let pid = parts.next()?.parse().ok()?;
+        let host = parts.next()?.to_string();
+        Some(Holder { pid, host })
+    }
+}
+
+impl fmt::Display for Holder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "process {} on {}", self.pid, self.host)
+    }
+}
+
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if result != 0 {
+        return "localhost".to_string();
+    }
+    let len = buf.iter().position(|byte| *byte == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+/// The lock on a document and its recovery file, an exclusive `flock` on `<file>.lock` held until
+/// it is dropped. The system releases it if the session crashes.
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    /// The lock is released once the file is closed.
+    _file: File,
+}
+
+pub enum Acquired {
+    Lock(Lock),
+    /// Another session has opened the document, it is unknown until the session has written its
+    /// details.
+    Held(Option<Holder>),
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Takes the lock on the file without blocking, returns false if another session holds it.
+fn try_lock(file: &File) -> io::Result<bool> {
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+/// Whether the path still refers to the opened file, the previous holder removes it on release.
+fn is_current(path: &Path, file: &File) -> Result<bool> {
+    let opened = file
+        .metadata()
+        .with_context(|| format!("could not read {}", path.display()))?;
+    Ok(match fs::metadata(path) {
+        Ok(current) => current.dev() == opened.dev() && current.ino() == opened.ino(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+        Err(err) => return Err(err).with_context(|| format!("could not read {}", path.display())),
+    })
+}
+
+/// Locks the document, fails with its holder if another session has opened it.
+pub fn acquire(path: &Path) -> Result<Acquired> {
+    let path = lock_path(path);
+    loop {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            // the content belongs to the holder until the lock has been taken
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("could not open {}", path.display()))?;
+        if !try_lock(&file).with_context(|| format!("could not lock {}", path.display()))? {
+            let mut content = String::new();
+            file.read_to_string(&mut content)
+                .with_context(|| format!("could not read {}", path.display()))?;
+            return Ok(Acquired::Held(Holder::parse(&content)));
+        }
+        if !is_current(&path, &file)? {
+            continue;
+        }
+        let holder = Holder::current();
+        file.set_len(0)
+            .and_then(|_| writeln!(file, "{} {}", holder.pid, holder.host))
+            .and_then(|_| file.sync_all())
+            .with_context(|| format!("could not write {}", path.display()))?;
+        return Ok(Acquired::Lock(Lock { path, _file: file }));
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // removed while the lock is still held, sessions which have opened it in the meantime
+        // notice that it is gone once they get the lock
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("igitt-lock-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("keywords.yml")
+    }
+
+    #[test]
+    fn second_session_sees_the_holder() {
+        let path = document("held");
+        let lock = match acquire(&path).unwrap() {
+            Acquired::Lock(lock) => lock,
+            Acquired::Held(_) => panic!("the document is not locked yet"),
+        };
+        match acquire(&path).unwrap() {
+            Acquired::Held(holder) => assert_eq!(holder, Some(Holder::current())),
+            Acquired::Lock(_) => panic!("the document has been locked twice"),
+        }
+        drop(lock);
+        assert!(!lock_path(&path).exists());
+        assert!(matches!(acquire(&path).unwrap(), Acquired::Lock(_)));
+    }
+
+    #[test]
+    fn half_written_lock_is_held() {
+        let path = document("half-written");
+        let file = File::create(lock_path(&path)).unwrap();
+        assert!(try_lock(&file).unwrap());
+        assert!(matches!(acquire(&path).unwrap(), Acquired::Held(None)));
+        assert!(lock_path(&path).exists());
+    }
+
+    #[test]
+    fn left_over_lock_is_taken_over() {
+        let path = document("left-over");
+        fs::write(lock_path(&path), "1 elsewhere\n").unwrap();
+        assert!(matches!(acquire(&path).unwrap(), Acquired::Lock(_)));
+        assert!(!lock_path(&path).exists());
+    }
+}
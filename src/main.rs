@@ -1,10 +1,39 @@
-use ansi_term::Color::{Cyan, Green};
+mod agreement;
+mod cache;
+mod config;
+mod diff;
+mod emit;
+mod fetch;
+mod forge;
+mod highlight;
+mod history;
+mod keys;
+mod labels;
+mod local;
+mod lock;
+mod merge;
+mod overview;
+mod prefetch;
+mod storage;
+mod validate;
+mod view;
+
+use agreement::{format_coefficient, Agreement, Unit};
+use cache::Cache;
+use config::Config;
+use fetch::{Fetcher, Unavailable};
+use forge::Forges;
+use history::{Edit, History};
+use keys::{Action, Binding, Bindings};
+use labels::LabelSchema;
+use prefetch::{Failure, Prefetcher};
+
+use ansi_term::Color::{Cyan, Green, Yellow};
 use anyhow::{bail, Context, Result};
-use async_std::prelude::*;
 use async_std::task;
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use cursive::align::HAlign;
-use cursive::theme::{PaletteColor, Style};
+use cursive::theme::{ColorStyle, PaletteColor, Style};
 use cursive::utils::span::SpannedString;
 use cursive::view::{Nameable, Resizable, Scrollable, View};
 use cursive::views::{
@@ -16,25 +45,54 @@ use cursive_aligned_view::Alignable;
 use cursive_async_view::{AsyncState, AsyncView};
 use cursive_tabs::TabPanel;
 use itertools::Itertools;
-use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use std::cmp::{Ord, Ordering};
 use std::collections::BTreeMap as Map;
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::Write;
-use std::sync::mpsc::channel;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "RatingRepr")]
 struct Rating {
-    is_refactoring: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    comment: String,
+}
+
+/// On-disk representation of a rating, older files store `is_refactoring` instead of a label.
+#[derive(Deserialize)]
+struct RatingRepr {
+    label: Option<String>,
+    is_refactoring: Option<bool>,
     comment: String,
 }
 
+impl From<RatingRepr> for Rating {
+    fn from(repr: RatingRepr) -> Self {
+        let is_refactoring = repr.is_refactoring;
+        let label = repr.label.or_else(|| {
+            is_refactoring.map(|is_refactoring| {
+                if is_refactoring {
+                    labels::REFACTORING.to_string()
+                } else {
+                    labels::NO_REFACTORING.to_string()
+                }
+            })
+        });
+        Rating {
+            label,
+            comment: repr.comment,
+        }
+    }
+}
+
 fn n_a() -> String {
     "N/A".to_string()
 }
@@ -43,11 +101,14 @@ fn moved() -> bool {
     false
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 struct Commit {
     origin: String,
     #[serde(default = "moved")]
     moved: bool,
+    /// Why igitt considers the commit as moved, not set if a rater has marked it by hand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    moved_reason: Option<String>,
     commit: String,
     #[serde(default = "n_a")]
     section: String,
@@ -55,12 +116,93 @@ struct Commit {
     time: String,
     #[serde(default)]
     rating: Map<String, Rating>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    adjudication: Option<Adjudication>,
+}
+
+/// The final decision on a commit whose ratings disagree.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Adjudication {
+    adjudicator: String,
+    label: String,
+    comment: String,
+}
+
+impl Commit {
+    /// A commit is conflicting if its raters have not chosen the same label.
+    fn is_conflicting(&self) -> bool {
+        !self.moved
+            && self
+                .rating
+                .values()
+                .filter_map(|rate| rate.label.as_ref())
+                .unique()
+                .count()
+                > 1
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+struct Settings {
+    #[serde(default)]
+    labels: LabelSchema,
+    /// Regular expressions highlighted in the commit messages of a keyword besides the keyword.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    patterns: Map<String, Vec<String>>,
+}
+
+/// A keywords file, settings are stored under the reserved `igitt` key next to the keywords.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Document {
+    #[serde(default, rename = "igitt", skip_serializing_if = "Option::is_none")]
+    settings: Option<Settings>,
+    #[serde(flatten)]
+    keywords: Map<String, Vec<Commit>>,
+}
+
+impl Document {
+    fn labels(&self) -> LabelSchema {
+        self.settings
+            .as_ref()
+            .map(|settings| settings.labels.clone())
+            .unwrap_or_default()
+    }
+
+    /// The patterns highlighted in the commit messages of every keyword, the keyword itself is
+    /// matched case-insensitively.
+    fn patterns(&self) -> Result<Map<String, Vec<Regex>>> {
+        let mut patterns = Map::new();
+        for keyword in self.keywords.keys() {
+            let mut regexes = vec![keyword_regex(keyword)];
+            if let Some(settings) = &self.settings {
+                for pattern in settings.patterns.get(keyword).into_iter().flatten() {
+                    regexes.push(Regex::new(pattern).with_context(|| {
+                        format!("invalid pattern '{}' of keyword '{}'", pattern, keyword)
+                    })?);
+                }
+            }
+            patterns.insert(keyword.clone(), regexes);
+        }
+        Ok(patterns)
+    }
+}
+
+/// Matches the keyword itself case-insensitively.
+fn keyword_regex(keyword: &str) -> Regex {
+    Regex::new(&format!("(?i){}", regex::escape(keyword))).expect("escaped keywords are valid")
 }
 
 enum Paging {
-    Next(String, bool, bool),
-    Prev(String, bool, bool),
-    Finish(String, bool, bool),
+    Next(String, Option<String>, bool),
+    Prev(String, Option<String>, bool),
+    Finish(String, Option<String>, bool),
+    /// Leaves the commit for the one at the given position.
+    Jump(usize, String, Option<String>, bool),
+    /// Stores the rating and writes the document without leaving the commit.
+    Save(String, Option<String>, bool),
+    /// Reverts the last change and goes to its commit, the shown rating is discarded.
+    Undo,
+    Redo,
 }
 
 #[derive(Clone)]
@@ -74,12 +216,20 @@ enum Load {
     No,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Rate,
+    View,
+    Adjudicate,
+}
+
 #[derive(Debug, Clone)]
 struct EvaluatedKeyword {
     keyword: String,
     true_positives: usize,
     false_positives: usize,
     unsure: usize,
+    agreement: Agreement,
 }
 
 enum EvaluationResult {
@@ -88,9 +238,11 @@ enum EvaluationResult {
     Unsure,
 }
 
+const CSV_HEADER: &str = "keyword,true_positives,false_positives,unsure,fleiss_kappa,krippendorff_alpha,mean_cohen_kappa,cohen_kappa\n";
+
 impl EvaluatedKeyword {
     fn to_colored_string(&self) -> String {
-        format!(
+        let mut content = format!(
             "{}:\n  {}: {}\n  {}: {}\n  Unsure: {}\n",
             self.keyword,
             Green.paint("True Positives"),
@@ -98,7 +250,24 @@ impl EvaluatedKeyword {
             Cyan.paint("False Positives"),
             self.false_positives,
             self.unsure,
-        )
+        );
+        content.push_str(&format!(
+            "  {}: {}\n  {}: {}\n",
+            Yellow.paint("Fleiss' Kappa"),
+            format_coefficient(self.agreement.fleiss),
+            Yellow.paint("Krippendorff's Alpha"),
+            format_coefficient(self.agreement.krippendorff),
+        ));
+        for (first, second, kappa) in &self.agreement.cohen {
+            content.push_str(&format!(
+                "  {} {}/{}: {}\n",
+                Yellow.paint("Cohen's Kappa"),
+                first,
+                second,
+                format_coefficient(*kappa),
+            ));
+        }
+        content
     }
 
     fn to_styled_string(&self) -> SpannedString<Style> {
@@ -118,124 +287,575 @@ impl EvaluatedKeyword {
             format!("  Unsure: {}\n", self.unsure),
             PaletteColor::Primary,
         );
+        content.append_styled(
+            format!(
+                "  Fleiss' Kappa: {}\n  Krippendorff's Alpha: {}\n",
+                format_coefficient(self.agreement.fleiss),
+                format_coefficient(self.agreement.krippendorff),
+            ),
+            PaletteColor::Primary,
+        );
+        for (first, second, kappa) in &self.agreement.cohen {
+            content.append_styled(
+                format!(
+                    "  Cohen's Kappa {}/{}: {}\n",
+                    first,
+                    second,
+                    format_coefficient(*kappa)
+                ),
+                PaletteColor::Primary,
+            );
+        }
         content
     }
 
     fn to_csv_row(&self) -> String {
+        let cohen = self
+            .agreement
+            .cohen
+            .iter()
+            .map(|(first, second, kappa)| {
+                format!("{}/{}={}", first, second, format_coefficient(*kappa))
+            })
+            .join(";");
         format!(
-            "{},{},{},{}\n",
-            self.keyword, self.true_positives, self.false_positives, self.unsure
+            "{},{},{},{},{},{},{},\"{}\"\n",
+            self.keyword,
+            self.true_positives,
+            self.false_positives,
+            self.unsure,
+            format_coefficient(self.agreement.fleiss),
+            format_coefficient(self.agreement.krippendorff),
+            format_coefficient(self.agreement.mean_cohen()),
+            cohen.replace('"', "\"\""),
         )
     }
 }
 
-async fn evaluate_keywords(keywords: Map<String, Vec<Commit>>) -> Vec<EvaluatedKeyword> {
-    keywords
+fn evaluate_commit(commit: &Commit, labels: &LabelSchema) -> Option<EvaluationResult> {
+    if commit.moved {
+        return None;
+    }
+    if let Some(adjudication) = &commit.adjudication {
+        return if labels.is_positive(&adjudication.label) {
+            Some(EvaluationResult::TruePositive)
+        } else {
+            Some(EvaluationResult::FalsePositive)
+        };
+    }
+    let found_results = commit
+        .rating
+        .values()
+        .filter_map(|rate| rate.label.as_ref())
+        .fold((0, 0), |(positive, negative), label| {
+            if labels.is_positive(label) {
+                (positive + 1, negative)
+            } else {
+                (positive, negative + 1)
+            }
+        });
+    match found_results.0.cmp(&found_results.1) {
+        Ordering::Greater => Some(EvaluationResult::TruePositive),
+        Ordering::Equal => Some(EvaluationResult::Unsure),
+        Ordering::Less => Some(EvaluationResult::FalsePositive),
+    }
+}
+
+/// The labels given by each rater, moved commits are not taken into account.
+fn agreement_unit(commit: &Commit) -> Option<Unit> {
+    if commit.moved {
+        return None;
+    }
+    Some(
+        commit
+            .rating
+            .iter()
+            .filter_map(|(rater, rate)| Some((rater.clone(), rate.label.clone()?)))
+            .collect(),
+    )
+}
+
+impl EvaluatedKeyword {
+    fn evaluate<'a>(
+        keyword: String,
+        commits: impl Iterator<Item = &'a Commit>,
+        labels: &LabelSchema,
+    ) -> Self {
+        let commits: Vec<&Commit> = commits.collect();
+        let evaluated_commits: Vec<EvaluationResult> = commits
+            .iter()
+            .filter_map(|commit| evaluate_commit(commit, labels))
+            .collect();
+        let units: Vec<Unit> = commits
+            .iter()
+            .filter_map(|commit| agreement_unit(commit))
+            .collect();
+        EvaluatedKeyword {
+            keyword,
+            true_positives: evaluated_commits.iter().fold(0, |acc, elem| match elem {
+                EvaluationResult::TruePositive => acc + 1,
+                _ => acc,
+            }),
+            false_positives: evaluated_commits.iter().fold(0, |acc, elem| match elem {
+                EvaluationResult::FalsePositive => acc + 1,
+                _ => acc,
+            }),
+            unsure: evaluated_commits.iter().fold(0, |acc, elem| match elem {
+                EvaluationResult::Unsure => acc + 1,
+                _ => acc,
+            }),
+            agreement: Agreement::from_units(&units),
+        }
+    }
+}
+
+async fn evaluate_keywords(
+    keywords: Map<String, Vec<Commit>>,
+    labels: LabelSchema,
+) -> Vec<EvaluatedKeyword> {
+    let mut evaluated: Vec<EvaluatedKeyword> = keywords
         .iter()
         .flat_map(|(keyword, all_commits)| {
-            let evaluated_commits: Vec<EvaluationResult> = all_commits
-                .iter()
-                .filter_map(|commit| {
-                    if commit.moved {
-                        return None;
-                    }
-                    let found_results =
-                        commit
-                            .rating
-                            .iter()
-                            .fold((0, 0), |(positive, negative), (_, rate)| {
-                                if rate.is_refactoring {
-                                    (positive + 1, negative)
-                                } else {
-                                    (positive, negative + 1)
-                                }
-                            });
-                    match found_results.0.cmp(&found_results.1) {
-                        Ordering::Greater => Some(EvaluationResult::TruePositive),
-                        Ordering::Equal => Some(EvaluationResult::Unsure),
-                        Ordering::Less => Some(EvaluationResult::FalsePositive),
-                    }
-                })
-                .collect();
-
-            let groups = all_commits
-                .iter()
-                .group_by(|commit| &commit.section);
+            let groups = all_commits.iter().group_by(|commit| &commit.section);
             let mut evaluated_sections: Vec<EvaluatedKeyword> = groups
                 .into_iter()
-                .map(move |(section, group)| {
-                    let evaluated_commits: Vec<EvaluationResult> = group
-                        .filter_map(|commit| {
-                            if commit.moved {
-                                return None;
-                            }
-                            let found_results = commit.rating
-                                .iter()
-                                .fold((0, 0), |(positive, negative), (_, rate)| {
-                                    if rate.is_refactoring {
-                                        (positive + 1, negative)
-                                    } else {
-                                        (positive, negative + 1)
-                                    }
-                                });
-                            match found_results.0.cmp(&found_results.1) {
-                                Ordering::Greater => Some(EvaluationResult::TruePositive),
-                                Ordering::Equal => Some(EvaluationResult::Unsure),
-                                Ordering::Less => Some(EvaluationResult::FalsePositive),
-                            }
-                        })
-                        .collect();
-
-                    EvaluatedKeyword {
-                        keyword: format!("{}/{}", keyword, section),
-                        true_positives: evaluated_commits.iter().fold(0, |acc, elem| match elem {
-                            EvaluationResult::TruePositive => acc + 1,
-                            _ => acc,
-                        }),
-                        false_positives: evaluated_commits.iter().fold(0, |acc, elem| match elem {
-                            EvaluationResult::FalsePositive => acc + 1,
-                            _ => acc,
-                        }),
-                        unsure: evaluated_commits.iter().fold(0, |acc, elem| match elem {
-                            EvaluationResult::Unsure => acc + 1,
-                            _ => acc,
-                        }),
-                    }
+                .map(|(section, group)| {
+                    EvaluatedKeyword::evaluate(format!("{}/{}", keyword, section), group, &labels)
                 })
                 .collect();
 
-            evaluated_sections.push(
-                EvaluatedKeyword {
-                    keyword: keyword.to_string(),
-                    true_positives: evaluated_commits.iter().fold(0, |acc, elem| match elem {
-                        EvaluationResult::TruePositive => acc + 1,
-                        _ => acc,
-                    }),
-                    false_positives: evaluated_commits.iter().fold(0, |acc, elem| match elem {
-                        EvaluationResult::FalsePositive => acc + 1,
-                        _ => acc,
-                    }),
-                    unsure: evaluated_commits.iter().fold(0, |acc, elem| match elem {
-                        EvaluationResult::Unsure => acc + 1,
-                        _ => acc,
-                    }),
-                }
-            );
+            evaluated_sections.push(EvaluatedKeyword::evaluate(
+                keyword.to_string(),
+                all_commits.iter(),
+                &labels,
+            ));
 
             evaluated_sections
         })
-        .collect()
+        .collect();
+    evaluated.push(EvaluatedKeyword::evaluate(
+        "overall".to_string(),
+        keywords.values().flatten(),
+        &labels,
+    ));
+    evaluated
 }
 
-fn save_csv(result: Vec<EvaluatedKeyword>, path: String) -> Result<()> {
+fn save_csv(result: &[EvaluatedKeyword], path: &str) -> Result<()> {
     let mut csv_file = File::create(path)?;
-    csv_file.write_all("keyword,true_positives,false_positives,unsure\n".as_bytes())?;
+    csv_file.write_all(CSV_HEADER.as_bytes())?;
     for row in result.iter().map(|entry| entry.to_csv_row()) {
         csv_file.write_all(row.as_bytes())?;
     }
     Ok(())
 }
 
+/// Reads the comment, the selected label and the moved flag from the rating panel.
+fn read_rating(siv: &mut Cursive, labels: &LabelSchema) -> (String, Option<String>, bool) {
+    let comment = siv
+        .find_name::<TextArea>("comment")
+        .unwrap()
+        .get_content()
+        .to_string();
+    let label = labels
+        .iter()
+        .find(|label| {
+            siv.find_name::<RadioButton<Option<String>>>(&format!("label_{}", label.id))
+                .unwrap()
+                .is_selected()
+        })
+        .map(|label| label.id.clone());
+    let moved = siv
+        .find_name::<RadioButton<Option<String>>>("is_broken")
+        .unwrap()
+        .is_selected();
+    (comment, label, moved)
+}
+
+/// Sends the rating of the shown commit along with the paging request, unless the commit is
+/// still loading.
+fn leave<F>(siv: &mut Cursive, paging_tx: &Sender<Paging>, labels: &LabelSchema, paging: F)
+where
+    F: FnOnce(String, Option<String>, bool) -> Paging,
+{
+    if lock_paging(siv) {
+        let (comment, label, moved) = read_rating(siv, labels);
+        paging_tx.send(paging(comment, label, moved)).unwrap();
+    }
+}
+
+/// Disables the paging buttons, returns false if they already are since a commit is loading.
+fn lock_paging(siv: &mut Cursive) -> bool {
+    let loaded = ["prev", "next", "finish"]
+        .iter()
+        .any(|name| matches!(siv.find_name::<Button>(name), Some(button) if button.is_enabled()));
+    if loaded {
+        siv.find_name::<Button>("prev").unwrap().disable();
+        siv.find_name::<Button>("next").unwrap().disable();
+        siv.find_name::<Button>("finish").unwrap().disable();
+    }
+    loaded
+}
+
+/// Activates a button as if it had been pressed.
+fn press(siv: &mut Cursive, name: &str) {
+    let button = siv.find_name::<Button>(name);
+    if let Some(mut valid) = button {
+        if valid.is_enabled() {
+            let result = valid.on_event(cursive::event::Event::Key(cursive::event::Key::Enter));
+            drop(valid);
+            if let cursive::event::EventResult::Consumed(Some(cb)) = result {
+                cb(siv)
+            }
+        }
+    };
+}
+
+/// Everything the keybindings of the main view act on.
+#[derive(Clone)]
+struct Controls {
+    paging_tx: Sender<Paging>,
+    quit_tx: Sender<Quit>,
+    labels: LabelSchema,
+    overview: Arc<Mutex<overview::Overview>>,
+    history: Arc<Mutex<History<Commit>>>,
+    bindings: Bindings,
+}
+
+impl Controls {
+    /// Runs the binding, returns false if it doesn't apply in the current state.
+    fn run(&self, siv: &mut Cursive, binding: &Binding) -> bool {
+        let action = match binding {
+            Binding::Label(id) => {
+                select_radio(siv, &format!("label_{}", id));
+                return true;
+            }
+            Binding::Action(action) => action,
+        };
+        match action {
+            Action::Quit => {
+                let quit_tx_cp = self.quit_tx.clone();
+                let quit_tx_save = self.quit_tx.clone();
+                siv.add_layer(
+                    Dialog::text("Do you really want to quit?")
+                        .button("Save and Quit", move |_siv| {
+                            quit_tx_save.send(Quit::SaveAndQuit).unwrap()
+                        })
+                        .button("Quit", move |_siv| quit_tx_cp.send(Quit::Quit).unwrap())
+                        .button("No", move |siv| {
+                            siv.pop_layer();
+                        }),
+                );
+            }
+            Action::Save => leave(siv, &self.paging_tx, &self.labels, Paging::Save),
+            Action::Prev => press(siv, "prev"),
+            Action::Next => press(siv, "next"),
+            Action::NextUnrated => {
+                let next = self.overview.lock().unwrap().next_unrated();
+                match next {
+                    Some(pos) => leave(siv, &self.paging_tx, &self.labels, |c, l, m| {
+                        Paging::Jump(pos, c, l, m)
+                    }),
+                    None => siv.add_layer(Dialog::info("There are no other unrated commits")),
+                }
+            }
+            Action::Overview => {
+                let paging_tx = self.paging_tx.clone();
+                let labels = self.labels.clone();
+                overview::show(siv, &self.overview, move |siv, pos| {
+                    leave(siv, &paging_tx, &labels, |c, l, m| {
+                        Paging::Jump(pos, c, l, m)
+                    })
+                });
+            }
+            Action::Search => view::search_prompt(siv),
+            Action::NextMatch => return view::jump_to_match(siv, true),
+            Action::PrevMatch => return view::jump_to_match(siv, false),
+            Action::ToggleSideBySide => view::toggle_side_by_side(siv),
+            Action::Collapse => view::toggle_collapsed(siv),
+            Action::Unavailable => select_radio(siv, "is_broken"),
+            Action::Undo => {
+                let available = self.history.lock().unwrap().can_undo();
+                if !available {
+                    siv.add_layer(Dialog::info("There is nothing to undo"));
+                } else if lock_paging(siv) {
+                    self.paging_tx.send(Paging::Undo).unwrap();
+                }
+            }
+            Action::Redo => {
+                let available = self.history.lock().unwrap().can_redo();
+                if !available {
+                    siv.add_layer(Dialog::info("There is nothing to redo"));
+                } else if lock_paging(siv) {
+                    self.paging_tx.send(Paging::Redo).unwrap();
+                }
+            }
+            Action::Help => keys::show_help(siv, &self.bindings, &self.labels),
+        }
+        true
+    }
+}
+
+fn select_radio(siv: &mut Cursive, name: &str) {
+    if let Some(mut button) = siv.find_name::<RadioButton<Option<String>>>(name) {
+        if button.is_enabled() {
+            button.select();
+        }
+    }
+}
+
+/// The state of a commit from the view of the given rater, or adjudicator.
+fn status(commit: &Commit, mode: Mode, name: &str) -> overview::Status {
+    let done = match mode {
+        Mode::Adjudicate => commit.adjudication.is_some(),
+        Mode::Rate | Mode::View => commit.rating.contains_key(name),
+    };
+    if commit.moved {
+        overview::Status::Moved
+    } else if !done {
+        overview::Status::Unrated
+    } else if commit.is_conflicting() && commit.adjudication.is_none() {
+        overview::Status::Conflict
+    } else {
+        overview::Status::Rated
+    }
+}
+
+/// Builds the fetcher from the forge, token and cache options.
+fn fetcher(matches: &ArgMatches, config: Config, offline: bool) -> Result<Fetcher> {
+    let forges = Forges::new(
+        &config.forges,
+        matches
+            .value_of("github-token")
+            .map(str::to_string)
+            .or(config.tokens.github),
+        matches
+            .value_of("gitlab-token")
+            .map(str::to_string)
+            .or(config.tokens.gitlab),
+    );
+    let cache = matches
+        .value_of("cache-dir")
+        .map(PathBuf::from)
+        .or_else(cache::default_dir)
+        .map(Cache::new);
+    Ok(Fetcher::new(forges, cache, offline))
+}
+
+/// Downloads every commit of a keywords file into the cache directory.
+async fn prefetch_command(matches: &ArgMatches<'_>, sub_matches: &ArgMatches<'_>) -> Result<()> {
+    let keywords_yaml_path = sub_matches
+        .value_of("KEYWORDS_YAML")
+        .context("KEYWORDS_YAML not provided")?;
+    let jobs = sub_matches
+        .value_of("jobs")
+        .unwrap_or("8")
+        .parse::<usize>()
+        .context("the number of jobs has to be a number")?;
+    let document: Document = serde_yaml::from_reader(File::open(keywords_yaml_path)?)?;
+    let targets = document
+        .keywords
+        .values()
+        .flatten()
+        .map(|commit| (commit.origin.clone(), commit.commit.clone()))
+        .unique()
+        .collect::<Vec<_>>();
+    let total = targets.len();
+    let failures = prefetch::download(
+        fetcher(matches, Config::load(matches.value_of("config"))?, false)?,
+        targets,
+        jobs,
+    )
+    .await;
+    for Failure {
+        origin,
+        commit,
+        error,
+    } in &failures
+    {
+        println!("{} {}: {:#}", Yellow.paint(commit), origin, error);
+    }
+    println!(
+        "Stored {} of {} commits",
+        Green.paint((total - failures.len()).to_string()),
+        total
+    );
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Locks the document for this session, fails if another session has opened it.
+fn lock(path: &Path, hint: &str) -> Result<lock::Lock> {
+    match lock::acquire(path)? {
+        lock::Acquired::Lock(lock) => Ok(lock),
+        lock::Acquired::Held(holder) => bail!(
+            "{} is already opened by {}{}",
+            path.display(),
+            holder.map_or("another session".to_string(), |holder| holder.to_string()),
+            hint
+        ),
+    }
+}
+
+/// Marks all commits of a keywords file as moved which are no longer available.
+async fn check_availability_command(
+    matches: &ArgMatches<'_>,
+    sub_matches: &ArgMatches<'_>,
+) -> Result<()> {
+    let keywords_yaml_path = sub_matches
+        .value_of("KEYWORDS_YAML")
+        .context("KEYWORDS_YAML not provided")?;
+    let jobs = sub_matches
+        .value_of("jobs")
+        .unwrap_or("8")
+        .parse::<usize>()
+        .context("the number of jobs has to be a number")?;
+    let document_lock = lock(Path::new(keywords_yaml_path), "")?;
+    let mut document: Document = serde_yaml::from_reader(File::open(keywords_yaml_path)?)?;
+    let targets = document
+        .keywords
+        .values()
+        .flatten()
+        .filter(|commit| !commit.moved)
+        .map(|commit| (commit.origin.clone(), commit.commit.clone()))
+        .unique()
+        .collect::<Vec<_>>();
+    let total = targets.len();
+    let failures = prefetch::check(
+        fetcher(matches, Config::load(matches.value_of("config"))?, false)?,
+        targets,
+        jobs,
+    )
+    .await;
+    let mut reasons = Map::new();
+    let mut unchecked = 0;
+    for Failure {
+        origin,
+        commit,
+        error,
+    } in failures
+    {
+        match error.downcast_ref::<Unavailable>() {
+            Some(Unavailable(reason)) => {
+                println!("{} {}: {}", Yellow.paint(&commit), origin, reason);
+                reasons.insert((origin, commit), reason.clone());
+            }
+            None => {
+                println!("{} {}: could not be checked: {:#}", commit, origin, error);
+                unchecked += 1;
+            }
+        }
+    }
+    for commit in document.keywords.values_mut().flatten() {
+        if let Some(reason) = reasons.get(&(commit.origin.clone(), commit.commit.clone())) {
+            commit.moved = true;
+            commit.moved_reason = Some(reason.clone());
+        }
+    }
+    storage::save(Path::new(keywords_yaml_path), &emit::document(&document))?;
+    // released here, exiting below would skip the drop
+    drop(document_lock);
+    println!(
+        "Marked {} of {} commits as moved",
+        Yellow.paint(reasons.len().to_string()),
+        total
+    );
+    if unchecked > 0 {
+        println!("{} commits could not be checked", unchecked);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn read_document(path: &str) -> Result<Document> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("could not open {}", path))?;
+    // git passes an empty ancestor if both sides added the file
+    if content.trim().is_empty() {
+        return Ok(Document {
+            settings: None,
+            keywords: Map::new(),
+        });
+    }
+    serde_yaml::from_str(&content).with_context(|| format!("could not parse {}", path))
+}
+
+fn merge_command(sub_matches: &ArgMatches<'_>) -> Result<()> {
+    let output = sub_matches
+        .value_of("output")
+        .context("no output file given")?;
+    let documents = sub_matches
+        .values_of("KEYWORDS_YAML")
+        .context("KEYWORDS_YAML not provided")?
+        .map(|path| Ok((path.to_string(), read_document(path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let total = documents.len();
+    let (merged, conflicts) = merge::merge(documents)?;
+    if !conflicts.is_empty() {
+        for conflict in &conflicts {
+            println!("{}", conflict);
+        }
+        println!(
+            "{} conflicts, nothing has been written",
+            Yellow.paint(conflicts.len().to_string())
+        );
+        std::process::exit(1);
+    }
+    let _lock = lock(Path::new(output), "")?;
+    storage::save(Path::new(output), &emit::document(&merged))?;
+    println!("Merged {} files into {}", total, output);
+    Ok(())
+}
+
+fn merge_driver_command(sub_matches: &ArgMatches<'_>) -> Result<()> {
+    let path = |name| {
+        sub_matches
+            .value_of(name)
+            .context("merge driver called without all files")
+    };
+    let (base, ours, theirs) = (path("ANCESTOR")?, path("CURRENT")?, path("OTHER")?);
+    let (merged, conflicts) = merge::merge3(
+        read_document(base)?,
+        read_document(ours)?,
+        read_document(theirs)?,
+    );
+    // git expects the result in the current version, conflicts leave the file unmerged
+    storage::write(Path::new(ours), &emit::document(&merged))?;
+    if !conflicts.is_empty() {
+        for conflict in &conflicts {
+            eprintln!("{}", conflict);
+        }
+        eprintln!(
+            "{} conflicts, the ratings of the current branch have been kept",
+            Yellow.paint(conflicts.len().to_string())
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn validate_command(matches: &ArgMatches<'_>, sub_matches: &ArgMatches<'_>) -> Result<()> {
+    let path = sub_matches
+        .value_of("KEYWORDS_YAML")
+        .context("KEYWORDS_YAML not provided")?;
+    let config = Config::load(matches.value_of("config"))?;
+    let forges = Forges::new(&config.forges, None, None);
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("could not open {}", path))?;
+    let problems = validate::validate(&content, &forges);
+    if problems.is_empty() {
+        println!("{} is valid", path);
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}:{}: {}", path, problem.line, problem.message);
+    }
+    println!("{} problems", Yellow.paint(problems.len().to_string()));
+    std::process::exit(1);
+}
+
 #[async_std::main]
 async fn main() -> Result<()> {
     let matches = App::new("igitt")
@@ -249,8 +869,13 @@ async fn main() -> Result<()> {
 Get a GitLab access token here (scope api):
     https://gitlab.com/profile/personal_access_tokens
 
+Tokens are only needed for the forges you fetch commits from, instead of
+passing them on the command line they can be set in the environment or
+in the config file.
+
 ",
         )
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("KEYWORDS_YAML")
                 .help("Sets the path to the keywords yaml file")
@@ -261,20 +886,58 @@ Get a GitLab access token here (scope api):
                 .help("Sets the GitHub API Token")
                 .long("github")
                 .value_name("TOKEN")
-                .takes_value(true)
-                .required(true),
+                .env("IGITT_GITHUB_TOKEN")
+                .hide_env_values(true)
+                .takes_value(true),
         )
         .arg(
             Arg::with_name("gitlab-token")
                 .help("Sets the GitLab API Token")
                 .long("gitlab")
                 .value_name("TOKEN")
-                .takes_value(true)
-                .required(true),
+                .env("IGITT_GITLAB_TOKEN")
+                .hide_env_values(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("config")
+                .help("Sets the path to the config file [default: ~/.config/igitt/config.yaml]")
+                .long("config")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("cache-dir")
+                .help("Sets the directory fetched commits are cached in [default: ~/.cache/igitt]")
+                .long("cache-dir")
+                .value_name("DIR")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("offline")
+                .help("Only shows commits from local repositories or the cache")
+                .long("offline"),
+        )
+        .arg(
+            Arg::with_name("prefetch")
+                .help("Sets how many of the following commits are fetched in the background [default: 3]")
+                .long("prefetch")
+                .value_name("N")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("read-only")
+                .help("Opens the file without locking it, ratings can only be viewed")
+                .long("read-only"),
+        )
+        .arg(
+            Arg::with_name("unrated-only")
+                .help("Only shows the commits which haven't been rated by you yet")
+                .long("unrated-only"),
         )
         .arg(
             Arg::with_name("evaluate")
-                .help("Evaluates true positives, false positives, and unsure values")
+                .help("Evaluates true positives, false positives, unsure values and inter-rater agreement")
                 .long("evaluate")
                 .short("e"),
         )
@@ -286,34 +949,140 @@ Get a GitLab access token here (scope api):
                 .long("csv")
                 .takes_value(true),
         )
+        .subcommand(
+            SubCommand::with_name("prefetch")
+                .about("Downloads all commits of a keywords file into the cache directory")
+                .arg(
+                    Arg::with_name("KEYWORDS_YAML")
+                        .help("Sets the path to the keywords yaml file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("jobs")
+                        .help("Sets how many commits are downloaded at once [default: 8]")
+                        .long("jobs")
+                        .short("j")
+                        .value_name("N")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check-availability")
+                .about("Marks all commits of a keywords file as moved which are no longer available")
+                .arg(
+                    Arg::with_name("KEYWORDS_YAML")
+                        .help("Sets the path to the keywords yaml file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("jobs")
+                        .help("Sets how many commits are checked at once [default: 8]")
+                        .long("jobs")
+                        .short("j")
+                        .value_name("N")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("Combines the ratings of several keywords files")
+                .arg(
+                    Arg::with_name("KEYWORDS_YAML")
+                        .help("Sets the paths to the keywords yaml files")
+                        .required(true)
+                        .multiple(true)
+                        .min_values(2),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("Sets the path the merged file is written to")
+                        .long("output")
+                        .short("o")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("merge-driver")
+                .about("Merges concurrent changes of a keywords file, to be called by git as merge driver")
+                .arg(
+                    Arg::with_name("ANCESTOR")
+                        .help("Sets the path to the common ancestor (%O)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("CURRENT")
+                        .help("Sets the path to the current version, which the result is written to (%A)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("OTHER")
+                        .help("Sets the path to the version being merged (%B)")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Checks a keywords file and lists all problems found")
+                .arg(
+                    Arg::with_name("KEYWORDS_YAML")
+                        .help("Sets the path to the keywords yaml file")
+                        .required(true),
+                ),
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        ("prefetch", Some(sub_matches)) => return prefetch_command(&matches, sub_matches).await,
+        ("check-availability", Some(sub_matches)) => {
+            return check_availability_command(&matches, sub_matches).await
+        }
+        ("merge", Some(sub_matches)) => return merge_command(sub_matches),
+        ("merge-driver", Some(sub_matches)) => return merge_driver_command(sub_matches),
+        ("validate", Some(sub_matches)) => return validate_command(&matches, sub_matches),
+        _ => {}
+    }
+
     let keywords_yaml_path = matches
         .value_of("KEYWORDS_YAML")
         .context("KEYWORDS_YAML not provided")?
         .to_string();
-    let keywords_tmp_path = ".#igitt.yml";
-    let mut keywords: Map<String, Vec<Commit>> =
-        serde_yaml::from_reader(File::open(&keywords_yaml_path)?)?;
-    let tmp_keywords: Option<Map<String, Vec<Commit>>> = {
+    let keywords_tmp_path = storage::recovery_path(Path::new(&keywords_yaml_path));
+    let evaluation = matches.is_present("evaluate");
+    let read_only = matches.is_present("read-only");
+    // the lock also covers the recovery file, which belongs to the session holding it, so it is
+    // taken before either is read
+    let _lock = if read_only || evaluation {
+        None
+    } else {
+        Some(lock(
+            Path::new(&keywords_yaml_path),
+            ", pass --read-only to view it anyway",
+        )?)
+    };
+    let mut document: Document = serde_yaml::from_reader(File::open(&keywords_yaml_path)?)?;
+    let tmp_document: Option<Document> = {
         let file = File::open(&keywords_tmp_path).map_or(None, Some);
         file.and_then(|x| serde_yaml::from_reader(x).map_or(None, Some))
     };
-    let commits = keywords.values().flatten();
+    let labels = document.labels();
+    labels.validate().context("invalid label schema")?;
+    let patterns = document.patterns()?;
+    let commits = document.keywords.values().flatten();
     let authors = commits
         .flat_map(|c| c.rating.keys().cloned())
         .collect::<HashSet<_>>();
-    let github_token = matches
-        .value_of("github-token")
-        .context("github-token not defined")?;
-    let gitlab_token = matches
-        .value_of("gitlab-token")
-        .context("gitlab-token not defined")?;
-    let evaluation = matches.is_present("evaluate");
+    let conflicts = document
+        .keywords
+        .values()
+        .flatten()
+        .filter(|commit| commit.is_conflicting())
+        .count();
     let csv_path = matches.value_of("csv");
 
     if evaluation {
-        let evaluation_result = evaluate_keywords(keywords.clone()).await;
+        let evaluation_result = evaluate_keywords(document.keywords.clone(), labels).await;
         println!(
             "{}",
             evaluation_result
@@ -324,23 +1093,36 @@ Get a GitLab access token here (scope api):
                 })
         );
         if let Some(valid_csv_path) = csv_path {
-            let mut csv_file = File::create(valid_csv_path)?;
-            csv_file.write_all("keyword,true_positives,false_positives,unsure\n".as_bytes())?;
-            for row in evaluation_result.iter().map(|entry| entry.to_csv_row()) {
-                csv_file.write_all(row.as_bytes())?;
-            }
+            save_csv(&evaluation_result, valid_csv_path)?;
             println!("Saved as csv in {}", valid_csv_path);
         }
         std::process::exit(0);
     }
 
-    let url_re = Regex::new(r"^https://(.+?)/(.+?)(:?\.git)?$")?;
+    let config = Config::load(matches.value_of("config"))?;
+    let bindings = Bindings::new(&config.keys, &labels).context("invalid keybindings")?;
+    // the recovery file may declare other labels and keywords, it is ignored like an unreadable
+    // one if they are not valid
+    let tmp_document = tmp_document.and_then(|tmp| {
+        let labels = tmp.labels();
+        labels.validate().ok()?;
+        let patterns = tmp.patterns().ok()?;
+        let bindings = Bindings::new(&config.keys, &labels).ok()?;
+        Some((tmp, labels, patterns, bindings))
+    });
+    let fetcher = fetcher(&matches, config, matches.is_present("offline"))?;
+    let prefetch_window = matches
+        .value_of("prefetch")
+        .unwrap_or("3")
+        .parse::<usize>()
+        .context("the prefetch window has to be a number")?;
 
     let (cb_sink_tx, cb_sink_rx) = channel();
-    let (readonly_name_tx, readonly_name_rx) = channel();
+    let (mode_name_tx, mode_name_rx) = channel();
     let (load_tx, load_rx) = channel();
-    let tmp_found = tmp_keywords.is_some();
-    let evaluation_keywords = keywords.clone();
+    let tmp_found = !read_only && tmp_document.is_some();
+    let evaluation_keywords = document.keywords.clone();
+    let evaluation_labels = labels.clone();
     let siv_task_handle = task::spawn(async move {
         let mut siv = Cursive::default();
         cb_sink_tx.send(siv.cb_sink().clone()).unwrap();
@@ -348,17 +1130,19 @@ Get a GitLab access token here (scope api):
         let mut tabs = TabPanel::new();
 
         let mut new_tab = LinearLayout::vertical();
-        let readonly_name_new_tx = readonly_name_tx.clone();
-        let readonly_name_ok_tx = readonly_name_tx.clone();
-        let readonly_name_view_tx = readonly_name_tx.clone();
-        let readonly_name_edit_tx = readonly_name_tx.clone();
+        let mode_name_new_tx = mode_name_tx.clone();
+        let mode_name_ok_tx = mode_name_tx.clone();
+        let mode_name_view_tx = mode_name_tx.clone();
+        let mode_name_edit_tx = mode_name_tx.clone();
+        let mode_name_adjudicate_tx = mode_name_tx.clone();
+        let mode_name_adjudicate_ok_tx = mode_name_tx.clone();
         new_tab.add_child(TextView::new("Please enter your name"));
         new_tab.add_child(
             EditView::new()
                 .on_submit(move |siv, name| {
                     if !name.is_empty() {
-                        readonly_name_new_tx
-                            .send((false, false, name.to_string()))
+                        mode_name_new_tx
+                            .send((Mode::Rate, false, false, name.to_string()))
                             .unwrap();
                         siv.pop_layer();
                     }
@@ -373,19 +1157,23 @@ Get a GitLab access token here (scope api):
                 .to_string();
 
             if !name.is_empty() {
-                readonly_name_ok_tx.send((false, false, name)).unwrap();
+                mode_name_ok_tx
+                    .send((Mode::Rate, false, false, name))
+                    .unwrap();
                 siv.pop_layer();
             }
         }));
-        tabs.add_tab("New", new_tab);
+        if !read_only {
+            tabs.add_tab("New", new_tab);
+        }
 
         let mut view_tab = LinearLayout::vertical();
         view_tab.add_child(TextView::new(
             "Please select a rating (press enter) to view",
         ));
         let mut view_select = SelectView::new().on_submit(move |siv, author: &String| {
-            readonly_name_view_tx
-                .send((true, false, author.clone()))
+            mode_name_view_tx
+                .send((Mode::View, false, false, author.clone()))
                 .unwrap();
             siv.pop_layer();
         });
@@ -400,8 +1188,19 @@ Get a GitLab access token here (scope api):
             "Please select a rating (press enter) to edit",
         ));
         let mut edit_select = SelectView::new().on_submit(move |siv, author: &String| {
-            let send_edit_yes = readonly_name_edit_tx.clone();
-            let send_edit_no = readonly_name_edit_tx.clone();
+            let unrated_only = siv
+                .find_name::<Checkbox>("unrated_only")
+                .unwrap()
+                .is_checked();
+            if unrated_only {
+                mode_name_edit_tx
+                    .send((Mode::Rate, false, true, author.clone()))
+                    .unwrap();
+                siv.pop_layer();
+                return;
+            }
+            let send_edit_yes = mode_name_edit_tx.clone();
+            let send_edit_no = mode_name_edit_tx.clone();
             let author_name_yes = author.clone();
             let author_name_no = author.clone();
             siv.add_layer(
@@ -410,14 +1209,14 @@ Get a GitLab access token here (scope api):
                 ))
                 .button("Yes", move |s| {
                     send_edit_yes
-                        .send((false, true, author_name_yes.clone()))
+                        .send((Mode::Rate, true, false, author_name_yes.clone()))
                         .unwrap();
                     s.pop_layer();
                     s.pop_layer();
                 })
                 .button("No", move |s| {
                     send_edit_no
-                        .send((false, false, author_name_no.clone()))
+                        .send((Mode::Rate, false, false, author_name_no.clone()))
                         .unwrap();
                     s.pop_layer();
                     s.pop_layer();
@@ -428,7 +1227,49 @@ Get a GitLab access token here (scope api):
             edit_select.add_item(rating.clone(), rating.clone());
         }
         edit_tab.add_child(edit_select);
-        tabs.add_tab("Edit", edit_tab);
+        edit_tab.add_child(
+            LinearLayout::horizontal()
+                .child(Checkbox::new().with_name("unrated_only"))
+                .child(TextView::new("Only show commits you haven't rated yet")),
+        );
+        if !read_only {
+            tabs.add_tab("Edit", edit_tab);
+        }
+
+        let mut adjudicate_tab = LinearLayout::vertical();
+        adjudicate_tab.add_child(TextView::new(format!(
+            "{} commits have conflicting ratings, please enter your name to adjudicate them",
+            conflicts
+        )));
+        adjudicate_tab.add_child(
+            EditView::new()
+                .on_submit(move |siv, name| {
+                    if !name.is_empty() {
+                        mode_name_adjudicate_tx
+                            .send((Mode::Adjudicate, false, false, name.to_string()))
+                            .unwrap();
+                        siv.pop_layer();
+                    }
+                })
+                .with_name("adjudicator_text_field"),
+        );
+        adjudicate_tab.add_child(Button::new("Ok", move |siv| {
+            let name = siv
+                .find_name::<EditView>("adjudicator_text_field")
+                .unwrap()
+                .get_content()
+                .to_string();
+
+            if !name.is_empty() {
+                mode_name_adjudicate_ok_tx
+                    .send((Mode::Adjudicate, false, false, name))
+                    .unwrap();
+                siv.pop_layer();
+            }
+        }));
+        if !read_only {
+            tabs.add_tab("Adjudicate", adjudicate_tab);
+        }
 
         let evaluate_tab = LinearLayout::vertical()
             .child(
@@ -437,8 +1278,10 @@ Get a GitLab access token here (scope api):
                     .child(TextView::new("Export as csv")),
             )
             .child(Button::new("Evaluate", move |siv| {
-                let result =
-                    async_std::task::block_on(evaluate_keywords(evaluation_keywords.clone()));
+                let result = async_std::task::block_on(evaluate_keywords(
+                    evaluation_keywords.clone(),
+                    evaluation_labels.clone(),
+                ));
 
                 siv.add_layer(
                     Dialog::around(TextView::new(result.iter().fold(
@@ -458,7 +1301,7 @@ Get a GitLab access token here (scope api):
                     .is_checked()
                 {
                     let message;
-                    match save_csv(result, "results.csv".to_string()) {
+                    match save_csv(&result, "results.csv") {
                         Ok(_) => message = "Results saved succesfully under results.csv",
                         Err(_) => {
                             message =
@@ -471,7 +1314,7 @@ Get a GitLab access token here (scope api):
                 }
             }));
         tabs.add_tab("Evaluate", evaluate_tab);
-        tabs.set_active_tab("Edit")
+        tabs.set_active_tab(if read_only { "View" } else { "Edit" })
             .expect("Edit tab could not be found");
 
         siv.add_layer(tabs.max_width(60));
@@ -497,14 +1340,54 @@ Get a GitLab access token here (scope api):
     });
 
     let cb_sink = cb_sink_rx.recv().unwrap();
-    match load_rx.recv().unwrap() {
-        Load::UseTmp => keywords = tmp_keywords.unwrap(),
-        Load::No => {}
+    let (labels, patterns, bindings) = match load_rx.recv().unwrap() {
+        Load::UseTmp => {
+            let (tmp, labels, patterns, bindings) = tmp_document.unwrap();
+            document = tmp;
+            (labels, patterns, bindings)
+        }
+        Load::No => (labels, patterns, bindings),
+    };
+    let (mode, resume, unrated_only, name) = mode_name_rx.recv().unwrap();
+    let unrated_only = mode != Mode::View && (unrated_only || matches.is_present("unrated-only"));
+    let readonly = mode == Mode::View;
+
+    let keys = document.keywords.keys().cloned().collect::<Vec<_>>();
+    let rater = &name;
+    let positions = keys
+        .iter()
+        .enumerate()
+        .flat_map(|(key_idx, kw)| {
+            document.keywords[kw]
+                .iter()
+                .enumerate()
+                .filter(|(_, commit)| mode != Mode::Adjudicate || commit.is_conflicting())
+                .filter(move |(_, commit)| {
+                    !unrated_only || status(commit, mode, rater) == overview::Status::Unrated
+                })
+                .map(move |(commit_idx, _)| (key_idx, commit_idx))
+        })
+        .collect::<Vec<_>>();
+    if positions.is_empty() {
+        cb_sink
+            .send(Box::new(|siv| {
+                siv.add_layer(
+                    Dialog::text("There are no commits to show").button("Ok", |siv| siv.quit()),
+                );
+            }))
+            .unwrap();
+        siv_task_handle.await;
+        return Ok(());
     }
-    let (readonly, resume, name) = readonly_name_rx.recv().unwrap();
 
     let (paging_tx, paging_rx) = channel();
+    let overview = Arc::new(Mutex::new(overview::Overview::default()));
+    let ui_overview = overview.clone();
+    let history = Arc::new(Mutex::new(History::default()));
+    let ui_history = history.clone();
     let (quit_tx, quit_rx) = channel();
+    let ui_labels = labels.clone();
+    let ui_bindings = bindings.clone();
     cb_sink
         .send(Box::new(move |siv| {
             let mut keywords_layout = LinearLayout::vertical();
@@ -512,81 +1395,44 @@ Get a GitLab access token here (scope api):
 
             let mut buttons_layout = LinearLayout::horizontal();
             let prev_tx = paging_tx.clone();
+            let prev_labels = ui_labels.clone();
             buttons_layout.add_child(
                 Button::new("Prev", move |siv| {
                     siv.find_name::<Button>("prev").unwrap().disable();
                     siv.find_name::<Button>("next").unwrap().disable();
                     siv.find_name::<Button>("finish").unwrap().disable();
 
-                    let comment = siv
-                        .find_name::<TextArea>("comment")
-                        .unwrap()
-                        .get_content()
-                        .to_string();
-                    let is_refactoring = siv
-                        .find_name::<RadioButton<bool>>("is_refactoring")
-                        .unwrap()
-                        .is_selected();
-                    let moved = siv
-                        .find_name::<RadioButton<bool>>("is_broken")
-                        .unwrap()
-                        .is_selected();
-                    prev_tx
-                        .send(Paging::Prev(comment, is_refactoring, moved))
-                        .unwrap();
+                    let (comment, label, moved) = read_rating(siv, &prev_labels);
+                    prev_tx.send(Paging::Prev(comment, label, moved)).unwrap();
                 })
                 .disabled()
                 .with_name("prev"),
             );
             let next_tx = paging_tx.clone();
+            let next_labels = ui_labels.clone();
             buttons_layout.add_child(
                 Button::new("Next", move |siv| {
                     siv.find_name::<Button>("prev").unwrap().disable();
                     siv.find_name::<Button>("next").unwrap().disable();
                     siv.find_name::<Button>("finish").unwrap().disable();
 
-                    let comment = siv
-                        .find_name::<TextArea>("comment")
-                        .unwrap()
-                        .get_content()
-                        .to_string();
-                    let is_refactoring = siv
-                        .find_name::<RadioButton<bool>>("is_refactoring")
-                        .unwrap()
-                        .is_selected();
-                    let moved = siv
-                        .find_name::<RadioButton<bool>>("is_broken")
-                        .unwrap()
-                        .is_selected();
-                    next_tx
-                        .send(Paging::Next(comment, is_refactoring, moved))
-                        .unwrap();
+                    let (comment, label, moved) = read_rating(siv, &next_labels);
+                    next_tx.send(Paging::Next(comment, label, moved)).unwrap();
                 })
                 .disabled()
                 .with_name("next"),
             );
             let finish_tx = paging_tx.clone();
+            let finish_labels = ui_labels.clone();
             buttons_layout.add_child(
                 Button::new("Finish", move |siv| {
                     siv.find_name::<Button>("prev").unwrap().disable();
                     siv.find_name::<Button>("next").unwrap().disable();
                     siv.find_name::<Button>("finish").unwrap().disable();
 
-                    let comment = siv
-                        .find_name::<TextArea>("comment")
-                        .unwrap()
-                        .get_content()
-                        .to_string();
-                    let is_refactoring = siv
-                        .find_name::<RadioButton<bool>>("is_refactoring")
-                        .unwrap()
-                        .is_selected();
-                    let moved = siv
-                        .find_name::<RadioButton<bool>>("is_broken")
-                        .unwrap()
-                        .is_selected();
+                    let (comment, label, moved) = read_rating(siv, &finish_labels);
                     finish_tx
-                        .send(Paging::Finish(comment, is_refactoring, moved))
+                        .send(Paging::Finish(comment, label, moved))
                         .unwrap();
                 })
                 .disabled()
@@ -597,165 +1443,109 @@ Get a GitLab access token here (scope api):
                 .with_name("keywords_dialog")
                 .full_screen();
             siv.add_layer(keywords_dialog);
-            siv.add_global_callback('q', move |siv| {
-                let quit_tx_cp = quit_tx.clone();
-                let quit_tx_save = quit_tx.clone();
-                siv.add_layer(
-                    Dialog::text("Do you really want to quit?")
-                        .button("Save and Quit", move |_siv| {
-                            quit_tx_save.send(Quit::SaveAndQuit).unwrap()
-                        })
-                        .button("Quit", move |_siv| quit_tx_cp.send(Quit::Quit).unwrap())
-                        .button("No", move |siv| {
-                            siv.pop_layer();
-                        }),
-                );
-            });
-            siv.add_global_callback('y', move |siv| {
-                siv.find_name::<RadioButton<bool>>("is_refactoring")
-                    .and_then(|mut x| {
-                        if x.is_enabled() {
-                            x.select();
-                        }
-                        Some(x)
-                    });
-            });
-            siv.add_global_callback('n', move |siv| {
-                siv.find_name::<RadioButton<bool>>("is_not")
-                    .and_then(|mut x| {
-                        if x.is_enabled() {
-                            x.select();
-                        }
-                        Some(x)
-                    });
-            });
-            siv.add_global_callback('i', move |siv| {
-                siv.find_name::<RadioButton<bool>>("is_broken")
-                    .and_then(|mut x| {
-                        if x.is_enabled() {
-                            x.select();
-                        }
-                        Some(x)
-                    });
-            });
-            siv.add_global_callback(',', move |siv| {
-                let button = siv.find_name::<Button>("prev");
-                if let Some(mut valid) = button {
-                    if valid.is_enabled() {
-                        let result =
-                            valid.on_event(cursive::event::Event::Key(cursive::event::Key::Enter));
-                        drop(valid);
-                        if let cursive::event::EventResult::Consumed(Some(cb)) = result {
-                            cb(siv)
-                        }
-                    }
-                };
-            });
-            siv.add_global_callback('.', move |siv| {
-                let button = siv.find_name::<Button>("next");
-                if let Some(mut valid) = button {
-                    if valid.is_enabled() {
-                        let result =
-                            valid.on_event(cursive::event::Event::Key(cursive::event::Key::Enter));
-                        drop(valid);
-                        if let cursive::event::EventResult::Consumed(Some(cb)) = result {
-                            cb(siv)
+            siv.set_user_data(view::ViewState::default());
+            let controls = Controls {
+                paging_tx,
+                quit_tx,
+                labels: ui_labels,
+                overview: ui_overview,
+                history: ui_history,
+                bindings: ui_bindings.clone(),
+            };
+            for (key, bindings) in ui_bindings.iter() {
+                let controls = controls.clone();
+                let bindings = bindings.to_vec();
+                siv.add_global_callback(key, move |siv| {
+                    // a contextual action comes first and passes the key on if it doesn't apply
+                    for binding in &bindings {
+                        if controls.run(siv, binding) {
+                            break;
                         }
                     }
-                };
-            });
+                });
+            }
+            siv.add_global_callback(cursive::event::Key::Esc, view::clear_search);
         }))
         .unwrap();
 
     let mut save = None;
     let mut finished = false;
-    let keys = keywords.keys().cloned().collect::<Vec<_>>();
-    let key_len = keys.len();
-    let mut key_idx;
-    let mut commit_idx;
-    if resume {
-        let indexes = keywords
+    let targets = positions
+        .iter()
+        .map(|(key_idx, commit_idx)| {
+            let commit = &document.keywords[&keys[*key_idx]][*commit_idx];
+            (commit.origin.clone(), commit.commit.clone())
+        })
+        .collect();
+    let mut prefetcher = Prefetcher::new(fetcher.clone(), targets, prefetch_window);
+    let mut pos = if resume {
+        positions
             .iter()
-            .map(|(_, commits)| {
-                commits.iter().enumerate().fold(None, |acc, (id, commit)| {
-                    if commit.rating.get(&name).is_some() {
-                        Some(id)
-                    } else {
-                        acc
-                    }
-                })
+            .rposition(|(key_idx, commit_idx)| {
+                document.keywords[&keys[*key_idx]][*commit_idx]
+                    .rating
+                    .contains_key(&name)
             })
-            .enumerate()
-            .filter(|(_, entry)| entry.is_some())
-            .last();
-        if let Some(last_index) = indexes {
-            key_idx = last_index.0;
-            commit_idx = last_index.1.unwrap();
-        } else {
-            key_idx = 0;
-            commit_idx = 0;
-        }
+            .unwrap_or(0)
     } else {
-        key_idx = 0;
-        commit_idx = 0;
-    }
+        0
+    };
     'outer: loop {
+        let (key_idx, commit_idx) = positions[pos];
         let kw = &keys[key_idx];
-        let commits = keywords.get(kw).unwrap();
-        let commits_len = commits.len();
-        let commit = &commits[commit_idx];
-
-        let captures = url_re
-            .captures(&commit.origin)
-            .context("could not parse origin")?;
-        let domain = captures
-            .get(1)
-            .context("no valid domain for origin")?
-            .as_str();
-        let path = captures
-            .get(2)
-            .context("no valid path for origin")?
-            .as_str();
-        let urlenc = utf8_percent_encode(path, NON_ALPHANUMERIC);
-
-        let (msg_url, diff_url, auth) = match domain {
-            "github.com" => (
-                format!(
-                    "https://api.github.com/repos/{}/git/commits/{}",
-                    path, commit.commit
-                ),
-                format!("https://github.com/{}/commit/{}.diff", path, commit.commit),
-                ("Authorization", format!("token {}", github_token)),
-            ),
-            "gitlab.com" => (
-                format!(
-                    "https://gitlab.com/api/v4/projects/{}/repository/commits/{}",
-                    urlenc, commit.commit
-                ),
-                format!(
-                    "https://gitlab.com/{}/-/commit/{}.diff",
-                    path, commit.commit
-                ),
-                ("PRIVATE-TOKEN", gitlab_token.to_string()),
-            ),
-            d => bail!("invalid domain {}", d),
+        let commit = &document.keywords[kw][commit_idx];
+        let is_first = pos == 0;
+        let is_last = pos + 1 >= positions.len();
+
+        let slot = prefetcher.get(pos);
+        let progress = {
+            let mut overview = overview.lock().unwrap();
+            overview.entries = positions
+                .iter()
+                .map(|(key_idx, commit_idx)| {
+                    let commit = &document.keywords[&keys[*key_idx]][*commit_idx];
+                    overview::Entry {
+                        keyword: keys[*key_idx].clone(),
+                        section: commit.section.clone(),
+                        origin: commit.origin.clone(),
+                        commit: commit.commit.clone(),
+                        status: status(commit, mode, &name),
+                    }
+                })
+                .collect();
+            overview.current = pos;
+            format!(" | {}/{} rated", overview.progress(), positions.len())
         };
-
-        let message_request = surf::get(msg_url)
-            .set_header(auth.0, auth.1.clone())
-            .recv_json::<Value>();
-        let diff_request = surf::get(diff_url).set_header(auth.0, auth.1).recv_string();
-
-        let (tx, rx) = channel();
-        async_std::task::spawn(async move {
-            let result = message_request.join(diff_request).await;
-            tx.send(result).expect("sending over channel failed");
-        });
+        // filled by the view once the commit turns out to be no longer available
+        let detected_reason: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let view_detected_reason = detected_reason.clone();
 
         let keyword = kw.clone();
         let commit_clone = commit.clone();
-        let name_clone = name.clone();
+        let (current_label, current_comment) = match mode {
+            Mode::Adjudicate => commit
+                .adjudication
+                .as_ref()
+                .map(|adjudication| {
+                    (
+                        Some(adjudication.label.clone()),
+                        adjudication.comment.clone(),
+                    )
+                })
+                .unwrap_or_default(),
+            Mode::Rate | Mode::View => commit
+                .rating
+                .get(&name)
+                .map(|rating| (rating.label.clone(), rating.comment.clone()))
+                .unwrap_or_default(),
+        };
+        let labels = labels.clone();
         let inner_cb_sink = cb_sink.clone();
+        let fetcher = fetcher.clone();
+        let keyword_patterns = patterns
+            .get(kw)
+            .cloned()
+            .unwrap_or_else(|| vec![keyword_regex(kw)]);
 
         cb_sink
             .send(Box::new(move |mut siv| {
@@ -774,118 +1564,189 @@ Get a GitLab access token here (scope api):
                     commit = commit_clone.commit,
                 ));
 
+                let diff_format = view::DiffFormat::of(siv);
                 let async_view = AsyncView::new(&mut siv, move || {
-                    let (message_result, diff_result) = match rx.try_recv() {
-                        Ok(req) => req,
-                        Err(_) => return AsyncState::Pending,
+                    let fetched = match slot.lock().unwrap().take() {
+                        Some(fetched) => fetched,
+                        None => return AsyncState::Pending,
                     };
+                    let unavailable = fetch::unavailable(&fetched);
+                    *view_detected_reason.lock().unwrap() = unavailable.clone();
+                    let (message_result, diff_result) = fetched;
 
                     let mut linear = LinearLayout::vertical();
 
                     let message = match message_result {
-                        Ok(message) => message["message"]
-                            .as_str()
-                            .unwrap_or("!! Commit message not available !!")
-                            .to_string(),
-                        Err(err) => format!("{}", err),
+                        Ok(message) => {
+                            let matches = highlight::find(&message, &keyword_patterns);
+                            highlight::emphasize(
+                                &SpannedString::<Style>::plain(message),
+                                &matches,
+                                Style::from(ColorStyle::highlight()),
+                            )
+                        }
+                        Err(err) => SpannedString::<Style>::plain(format!("{}", err)),
                     };
 
                     linear.add_child(
                         Panel::new(
-                            TextView::new(message)
+                            TextView::new(message.clone())
                                 .with_name("message_field")
                                 .scrollable()
                                 .scroll_x(false)
-                                .scroll_y(true),
+                                .scroll_y(true)
+                                .with_name("message_scroll"),
                         )
                         .title("Commit Message")
                         .title_position(HAlign::Left),
                     );
 
-                    let diff = match diff_result {
-                        Ok(diff) => diff,
-                        Err(err) => format!("{}", err),
+                    if mode == Mode::Adjudicate {
+                        let mut verdicts_layout = LinearLayout::horizontal();
+                        for (rater, rating) in &commit_clone.rating {
+                            let verdict = rating
+                                .label
+                                .as_ref()
+                                .map(|id| {
+                                    labels
+                                        .get(id)
+                                        .map_or(id.clone(), |label| label.description.clone())
+                                })
+                                .unwrap_or_else(|| "No label".to_string());
+                            let mut content = SpannedString::new();
+                            content.append_styled(verdict, PaletteColor::Secondary);
+                            if !rating.comment.is_empty() {
+                                content.append_plain(format!("\n{}", rating.comment));
+                            }
+                            verdicts_layout.add_child(
+                                Panel::new(TextView::new(content).scrollable())
+                                    .title(rater.clone())
+                                    .title_position(HAlign::Left)
+                                    .full_width(),
+                            );
+                        }
+                        linear.add_child(verdicts_layout);
+                    }
+
+                    let diff_result = diff_result.map(|diff| diff::parse(&diff));
+                    let changed_files = diff_result.as_ref().map_or(0, |files| {
+                        files.iter().filter(|file| !file.path.is_empty()).count()
+                    });
+                    // the diff is rendered right away, its views can only be found by name once
+                    // the loaded view has been swapped in
+                    let (diff, file_labels, files) = match diff_result {
+                        Ok(files) => {
+                            let (rendered, file_labels) = view::render_diff(
+                                &diff_format,
+                                &files,
+                                &view::initially_collapsed(&files),
+                                changed_files > 1,
+                            );
+                            (rendered.text, file_labels, Some(files))
+                        }
+                        Err(err) => (
+                            SpannedString::<Style>::plain(format!("{}", err)),
+                            Vec::new(),
+                            None,
+                        ),
                     };
 
-                    linear.add_child(
-                        Panel::new(
-                            TextView::new(diff)
-                                .with_name("diff_field")
-                                .scrollable()
-                                .scroll_x(false)
-                                .scroll_y(true),
-                        )
-                        .title("Diff")
-                        .title_position(HAlign::Left)
-                        .full_screen(),
-                    );
+                    let mut diff_layout = LinearLayout::horizontal();
+                    if changed_files > 1 {
+                        let mut file_list = SelectView::<usize>::new()
+                            .on_submit(|siv, idx| view::jump_to_file(siv, *idx));
+                        for (label, idx) in file_labels {
+                            file_list.add_item(label, idx);
+                        }
+                        diff_layout.add_child(
+                            Panel::new(file_list.with_name("file_list").scrollable())
+                                .title("Files")
+                                .title_position(HAlign::Left)
+                                .fixed_width(view::FILE_LIST_WIDTH),
+                        );
+                    }
+                    diff_layout.add_child(view::diff_panel(diff).full_screen());
+                    linear.add_child(diff_layout.full_screen());
 
                     let mut rating_layout = LinearLayout::vertical();
                     let mut radio_group = RadioGroup::new();
+                    // an adjudication is only written once a label has been picked, the first
+                    // button of a group counts as selected until another one is
+                    let selected_label = match mode {
+                        Mode::Adjudicate => {
+                            let mut undecided_btn = radio_group.button(None, "No verdict yet");
+                            if commit_clone.moved || readonly {
+                                undecided_btn.disable();
+                            }
+                            rating_layout.add_child(undecided_btn);
+                            current_label.clone()
+                        }
+                        Mode::Rate | Mode::View => Some(
+                            current_label
+                                .clone()
+                                .unwrap_or_else(|| labels.default_label().id.clone()),
+                        ),
+                    };
 
-                    let mut valid_btn =
-                        radio_group.button(true, "This commit is a valid refactoring");
-                    let mut invalid_btn =
-                        radio_group.button(false, "This commit does not contain refactoring");
+                    for label in labels.iter() {
+                        let mut label_btn =
+                            radio_group.button(Some(label.id.clone()), label.description.clone());
+                        if commit_clone.moved || readonly {
+                            label_btn.disable();
+                        }
+                        if !commit_clone.moved && Some(&label.id) == selected_label.as_ref() {
+                            label_btn.select();
+                        }
+                        rating_layout.add_child(label_btn.with_name(format!("label_{}", label.id)));
+                    }
                     let mut broken_btn =
-                        radio_group.button(false, "This commit seems to be no longer available");
+                        radio_group.button(None, "This commit seems to be no longer available");
 
-                    let mut comment_area = TextArea::new().content(
-                        match commit_clone.rating.get_key_value(&name_clone) {
-                            Some(val) => val.1.comment.clone(),
-                            None => "".to_string(),
-                        },
-                    );
+                    let mut comment_area = TextArea::new().content(current_comment.clone());
 
-                    if commit_clone.moved {
-                        valid_btn.disable();
-                        invalid_btn.disable();
+                    if commit_clone.moved || unavailable.is_some() {
                         broken_btn.select();
-                    } else {
-                        match commit_clone.rating.get_key_value(&name_clone) {
-                            Some(val) => {
-                                if val.1.is_refactoring {
-                                    valid_btn.select();
-                                } else {
-                                    invalid_btn.select();
-                                }
-                            }
-                            None => {
-                                invalid_btn.select();
-                            }
-                        }
                     }
 
                     if readonly {
-                        valid_btn.disable();
-                        invalid_btn.disable();
                         broken_btn.disable();
                         comment_area.disable();
                     }
 
-                    rating_layout.add_child(valid_btn.with_name("is_refactoring"));
-                    rating_layout.add_child(invalid_btn.with_name("is_not"));
                     rating_layout.add_child(broken_btn.with_name("is_broken"));
                     rating_layout.add_child(TextView::new("\nComment:"));
                     rating_layout.add_child(comment_area.with_name("comment").min_height(3));
 
                     linear.add_child(
                         Panel::new(rating_layout)
-                            .title("Refactor rating")
+                            .title(if mode == Mode::Adjudicate {
+                                "Adjudication"
+                            } else {
+                                "Rating"
+                            })
                             .title_position(HAlign::Left),
                     );
 
                     let keyword = keyword.clone();
                     let commit_clone = commit_clone.clone();
+                    let progress = progress.clone();
+                    let quota = match fetcher.quota(&commit_clone.origin) {
+                        Some(quota) if quota.is_low() => {
+                            format!(" | API quota running out: {} left", quota)
+                        }
+                        Some(quota) => format!(" | API quota {}", quota),
+                        None => String::new(),
+                    };
                     inner_cb_sink
                         .send(Box::new(move |siv| {
+                            view::show_commit(siv, message, files);
                             siv.find_name::<Button>("prev").unwrap().enable();
                             siv.find_name::<Button>("next").unwrap().enable();
                             siv.find_name::<Button>("finish").unwrap().disable();
-                            if commit_idx == 0 && key_idx == 0 {
+                            if is_first {
                                 siv.find_name::<Button>("prev").unwrap().disable();
                             }
-                            if key_idx + 1 >= key_len && commit_idx + 1 >= commits_len {
+                            if is_last {
                                 siv.find_name::<Button>("next").unwrap().disable();
                                 siv.find_name::<Button>("finish").unwrap().enable();
                             }
@@ -893,12 +1754,14 @@ Get a GitLab access token here (scope api):
                                 .find_name::<Panel<LinearLayout>>("keywords_dialog")
                                 .unwrap();
                             keywords_dialog.set_title(format!(
-                                "'{keyword}' / {section} | {origin} @ {commit} - {date}",
+                                "'{keyword}' / {section} | {origin} @ {commit} - {date}{progress}{quota}",
                                 keyword = keyword,
                                 origin = commit_clone.origin,
                                 section = commit_clone.section,
                                 commit = commit_clone.commit,
                                 date = commit_clone.time,
+                                progress = progress,
+                                quota = quota,
                             ));
                         }))
                         .unwrap();
@@ -912,11 +1775,11 @@ Get a GitLab access token here (scope api):
             }))
             .unwrap();
 
-        let comment;
-        let is_refactoring;
-        let moved;
+        // the rating of the shown commit, unless it is discarded
+        let rating;
+        let shown = pos;
+        let mut saving = false;
 
-        let old_commit_idx = commit_idx;
         loop {
             match quit_rx.try_recv() {
                 Ok(Quit::Quit) => {
@@ -931,55 +1794,116 @@ Get a GitLab access token here (scope api):
             }
 
             match paging_rx.try_recv() {
-                Ok(Paging::Next(c, r, m)) => {
-                    comment = c;
-                    is_refactoring = r;
-                    moved = m;
-                    if commit_idx + 1 >= commits_len {
-                        key_idx += 1;
-                        commit_idx = 0;
-                    } else {
-                        commit_idx += 1;
-                    }
+                Ok(Paging::Next(c, l, m)) => {
+                    rating = Some((c, l, m));
+                    pos += 1;
                     break;
                 }
-                Ok(Paging::Prev(c, r, m)) => {
-                    comment = c;
-                    is_refactoring = r;
-                    moved = m;
-                    if commit_idx == 0 {
-                        key_idx -= 1;
-                        let kw = &keys[key_idx];
-                        let commits = keywords.get(kw).unwrap();
-                        commit_idx = commits.len() - 1;
-                    } else {
-                        commit_idx -= 1;
-                    }
+                Ok(Paging::Prev(c, l, m)) => {
+                    rating = Some((c, l, m));
+                    pos -= 1;
                     break;
                 }
-                Ok(Paging::Finish(c, r, m)) => {
-                    comment = c;
-                    is_refactoring = r;
-                    moved = m;
+                Ok(Paging::Jump(p, c, l, m)) => {
+                    rating = Some((c, l, m));
+                    pos = p;
+                    break;
+                }
+                Ok(Paging::Save(c, l, m)) => {
+                    rating = Some((c, l, m));
+                    saving = true;
+                    break;
+                }
+                Ok(Paging::Finish(c, l, m)) => {
+                    rating = Some((c, l, m));
                     save = Some(Quit::SaveAndQuit);
                     finished = true;
                     break;
                 }
+                Ok(Paging::Undo) => {
+                    if let Some(edit) = history.lock().unwrap().undo() {
+                        let (key_idx, commit_idx) = positions[edit.pos];
+                        document.keywords.get_mut(&keys[key_idx]).unwrap()[commit_idx] =
+                            edit.before.clone();
+                        pos = edit.pos;
+                    }
+                    rating = None;
+                    break;
+                }
+                Ok(Paging::Redo) => {
+                    if let Some(edit) = history.lock().unwrap().redo() {
+                        let (key_idx, commit_idx) = positions[edit.pos];
+                        document.keywords.get_mut(&keys[key_idx]).unwrap()[commit_idx] =
+                            edit.after.clone();
+                        pos = edit.pos;
+                    }
+                    rating = None;
+                    break;
+                }
                 Err(_) => thread::sleep(Duration::from_millis(10)),
             }
         }
 
-        keywords.get_mut(kw).unwrap()[old_commit_idx].moved = moved;
+        if let Some((comment, label, moved)) = rating {
+            let commit = &mut document.keywords.get_mut(kw).unwrap()[commit_idx];
+            let before = commit.clone();
+            let moved_reason = if moved {
+                detected_reason
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .or_else(|| commit.moved_reason.clone())
+            } else {
+                None
+            };
+            match mode {
+                Mode::View => {}
+                Mode::Rate => {
+                    commit.moved = moved;
+                    commit.moved_reason = moved_reason;
+                    commit
+                        .rating
+                        .insert(name.clone(), Rating { label, comment });
+                }
+                Mode::Adjudicate => {
+                    commit.moved = moved;
+                    commit.moved_reason = moved_reason;
+                    match label {
+                        Some(label) => {
+                            commit.adjudication = Some(Adjudication {
+                                adjudicator: name.clone(),
+                                label,
+                                comment,
+                            })
+                        }
+                        // the adjudicator has withdrawn the verdict
+                        None if !moved => commit.adjudication = None,
+                        None => {}
+                    }
+                }
+            }
+            if *commit != before {
+                history.lock().unwrap().record(Edit {
+                    pos: shown,
+                    before,
+                    after: commit.clone(),
+                });
+            }
+        }
 
-        keywords.get_mut(kw).unwrap()[old_commit_idx].rating.insert(
-            name.clone(),
-            Rating {
-                is_refactoring,
-                comment,
-            },
-        );
+        if !read_only {
+            storage::write(&keywords_tmp_path, &emit::document(&document))?;
+        }
 
-        serde_yaml::to_writer(File::create(&keywords_tmp_path)?, &keywords)?;
+        if saving && !read_only {
+            storage::save(Path::new(&keywords_yaml_path), &emit::document(&document))?;
+            let path = keywords_yaml_path.clone();
+            cb_sink
+                .send(Box::new(move |siv| {
+                    siv.add_layer(Dialog::info(format!("Rating saved to {}", path)));
+                }))
+                .unwrap();
+        }
 
         if finished {
             break 'outer;
@@ -987,11 +1911,11 @@ Get a GitLab access token here (scope api):
     }
 
     match save.clone() {
-        Some(Quit::SaveAndQuit) | Some(Quit::Quit) => {
-            serde_yaml::to_writer(File::create(&keywords_yaml_path)?, &keywords)?;
+        Some(Quit::SaveAndQuit) | Some(Quit::Quit) if !read_only => {
+            storage::save(Path::new(&keywords_yaml_path), &emit::document(&document))?;
             std::fs::remove_file(&keywords_tmp_path).ok();
         }
-        None => {}
+        _ => {}
     }
 
     cb_sink
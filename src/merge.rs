@@ -0,0 +1,568 @@
+use crate::{Adjudication, Commit, Document, Rating};
+use anyhow::{bail, Result};
+use itertools::Itertools;
+use std::collections::{BTreeMap as Map, HashSet};
+use std::fmt;
+
+/// A commit the merged files disagree on.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub keyword: String,
+    pub origin: String,
+    pub commit: String,
+    /// What the files disagree on, e.g. the rating of a rater.
+    pub subject: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}): {} is '{}' in one file but '{}' in another",
+            self.commit, self.origin, self.keyword, self.subject, self.ours, self.theirs
+        )
+    }
+}
+
+fn describe_rating(rating: &Rating) -> String {
+    let label = rating.label.as_deref().unwrap_or("no label");
+    if rating.comment.is_empty() {
+        label.to_string()
+    } else {
+        format!("{}: {}", label, rating.comment)
+    }
+}
+
+fn describe_adjudication(adjudication: &Adjudication) -> String {
+    format!("{} by {}", adjudication.label, adjudication.adjudicator)
+}
+
+/// Joins two comments on the same commit, paragraphs of the second one which the first one lacks
+/// are appended to it.
+fn join_comments(ours: &str, theirs: &str) -> String {
+    if theirs.is_empty() || ours == theirs {
+        return ours.to_string();
+    }
+    if ours.is_empty() {
+        return theirs.to_string();
+    }
+    let mut paragraphs: Vec<&str> = ours.split("\n\n").collect();
+    for paragraph in theirs.split("\n\n") {
+        if !paragraph.trim().is_empty() && !paragraphs.contains(&paragraph) {
+            paragraphs.push(paragraph);
+        }
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Combines two ratings of the same rater, `None` if they carry different labels. Their comments
+/// are joined.
+fn merge_rating(ours: &Rating, theirs: &Rating) -> Option<Rating> {
+    if ours.label != theirs.label {
+        return None;
+    }
+    Some(Rating {
+        label: ours.label.clone(),
+        comment: join_comments(&ours.comment, &theirs.comment),
+    })
+}
+
+/// Adds the ratings of another version of the commit, ratings which disagree are kept as they
+/// are and reported.
+fn merge_commit(keyword: &str, ours: &mut Commit, theirs: &Commit, conflicts: &mut Vec<Conflict>) {
+    let mut report = |subject: String, first: String, second: String| {
+        conflicts.push(Conflict {
+            keyword: keyword.to_string(),
+            origin: theirs.origin.clone(),
+            commit: theirs.commit.clone(),
+            subject,
+            ours: first,
+            theirs: second,
+        })
+    };
+    for (rater, rating) in &theirs.rating {
+        match ours.rating.get(rater) {
+            Some(existing) => match merge_rating(existing, rating) {
+                Some(merged) => {
+                    ours.rating.insert(rater.clone(), merged);
+                }
+                None => report(
+                    format!("the rating of {}", rater),
+                    describe_rating(existing),
+                    describe_rating(rating),
+                ),
+            },
+            None => {
+                ours.rating.insert(rater.clone(), rating.clone());
+            }
+        }
+    }
+    match (&ours.adjudication, &theirs.adjudication) {
+        (Some(first), Some(second)) if first != second => report(
+            "the adjudication".to_string(),
+            describe_adjudication(first),
+            describe_adjudication(second),
+        ),
+        (None, Some(_)) => ours.adjudication = theirs.adjudication.clone(),
+        _ => {}
+    }
+    // a commit which has been found to be unavailable once stays so
+    if theirs.moved && !ours.moved {
+        ours.moved = true;
+        ours.moved_reason = theirs.moved_reason.clone();
+    }
+}
+
+/// Merges the documents into the first one, commits are matched by their keyword, origin and
+/// hash. The documents are given along with their path to refer to them in errors.
+pub fn merge(documents: Vec<(String, Document)>) -> Result<(Document, Vec<Conflict>)> {
+    let mut documents = documents.into_iter();
+    let (first_path, mut merged) = match documents.next() {
+        Some(first) => first,
+        None => bail!("there are no files to merge"),
+    };
+    let mut conflicts = Vec::new();
+    for (path, document) in documents {
+        if document.labels() != merged.labels() {
+            bail!("{} declares other labels than {}", path, first_path);
+        }
+        if merged.settings.is_none() {
+            merged.settings = document.settings;
+        }
+        for (keyword, commits) in document.keywords {
+            let merged_commits = merged.keywords.entry(keyword.clone()).or_default();
+            for commit in commits {
+                let existing = merged_commits.iter_mut().find(|existing| {
+                    existing.origin == commit.origin && existing.commit == commit.commit
+                });
+                match existing {
+                    Some(existing) => merge_commit(&keyword, existing, &commit, &mut conflicts),
+                    None => merged_commits.push(commit),
+                }
+            }
+        }
+    }
+    Ok((merged, conflicts))
+}
+
+/// Commits are identified by their keyword, origin and hash.
+type Key = (String, String, String);
+
+fn index(document: &Document) -> Map<Key, &Commit> {
+    document
+        .keywords
+        .iter()
+        .flat_map(|(keyword, commits)| {
+            commits.iter().map(move |commit| {
+                (
+                    (
+                        keyword.clone(),
+                        commit.origin.clone(),
+                        commit.commit.clone(),
+                    ),
+                    commit,
+                )
+            })
+        })
+        .collect()
+}
+
+/// Picks the version of a value changed by one side, `None` if both sides changed it differently.
+fn pick<'a, T: PartialEq>(
+    base: Option<&'a T>,
+    ours: Option<&'a T>,
+    theirs: Option<&'a T>,
+) -> Option<Option<&'a T>> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+fn describe<T>(value: Option<&T>, describe: fn(&T) -> String) -> String {
+    value.map_or_else(|| "missing".to_string(), describe)
+}
+
+/// Merges the changes of both sides to a commit, `base` is missing if both sides added it.
+fn merge_commit3(
+    key: &Key,
+    base: Option<&Commit>,
+    ours: &Commit,
+    theirs: &Commit,
+    conflicts: &mut Vec<Conflict>,
+) -> Commit {
+    let mut report = |subject: String, first: String, second: String| {
+        conflicts.push(Conflict {
+            keyword: key.0.clone(),
+            origin: key.1.clone(),
+            commit: key.2.clone(),
+            subject,
+            ours: first,
+            theirs: second,
+        })
+    };
+    let mut merged = ours.clone();
+
+    let raters = ours
+        .rating
+        .keys()
+        .chain(theirs.rating.keys())
+        .chain(base.iter().flat_map(|base| base.rating.keys()))
+        .unique();
+    for rater in raters {
+        let (first, second) = (ours.rating.get(rater), theirs.rating.get(rater));
+        match pick(base.and_then(|base| base.rating.get(rater)), first, second) {
+            Some(Some(rating)) => {
+                merged.rating.insert(rater.clone(), rating.clone());
+            }
+            Some(None) => {
+                merged.rating.remove(rater);
+            }
+            None => {
+                // a rating which has been removed on one side is kept
+                if let Some(rating) = first.or(second) {
+                    merged.rating.insert(rater.clone(), rating.clone());
+                }
+                report(
+                    format!("the rating of {}", rater),
+                    describe(first, describe_rating),
+                    describe(second, describe_rating),
+                );
+            }
+        }
+    }
+
+    let adjudication = pick(
+        base.and_then(|base| base.adjudication.as_ref()),
+        ours.adjudication.as_ref(),
+        theirs.adjudication.as_ref(),
+    );
+    match adjudication {
+        Some(adjudication) => merged.adjudication = adjudication.cloned(),
+        None => report(
+            "the adjudication".to_string(),
+            describe(ours.adjudication.as_ref(), describe_adjudication),
+            describe(theirs.adjudication.as_ref(), describe_adjudication),
+        ),
+    }
+
+    let moved = |commit: &Commit| (commit.moved, commit.moved_reason.clone());
+    let base_moved = base.map(moved);
+    let (ours_moved, theirs_moved) = (moved(ours), moved(theirs));
+    match pick(base_moved.as_ref(), Some(&ours_moved), Some(&theirs_moved)) {
+        Some(Some((flag, reason))) => {
+            merged.moved = *flag;
+            merged.moved_reason = reason.clone();
+        }
+        // a commit which has been found to be unavailable on one side stays so
+        _ => {
+            merged.moved = ours.moved || theirs.moved;
+            merged.moved_reason = ours
+                .moved_reason
+                .clone()
+                .or_else(|| theirs.moved_reason.clone());
+        }
+    }
+
+    let section = pick(
+        base.map(|base| &base.section),
+        Some(&ours.section),
+        Some(&theirs.section),
+    );
+    match section {
+        Some(section) => merged.section = section.cloned().unwrap_or_default(),
+        None => report(
+            "the section".to_string(),
+            ours.section.clone(),
+            theirs.section.clone(),
+        ),
+    }
+    let time = pick(
+        base.map(|base| &base.time),
+        Some(&ours.time),
+        Some(&theirs.time),
+    );
+    match time {
+        Some(time) => merged.time = time.cloned().unwrap_or_default(),
+        None => report(
+            "the time".to_string(),
+            ours.time.clone(),
+            theirs.time.clone(),
+        ),
+    }
+    merged
+}
+
+/// Merges the changes both sides made to their common ancestor, as done by git on `git merge`.
+/// Changes to different ratings are combined, ratings changed differently on both sides are
+/// reported and the version of ours is kept.
+pub fn merge3(base: Document, ours: Document, theirs: Document) -> (Document, Vec<Conflict>) {
+    let mut conflicts = Vec::new();
+    let (base_commits, theirs_commits) = (index(&base), index(&theirs));
+    let mut merged = Document {
+        settings: match pick(
+            base.settings.as_ref(),
+            ours.settings.as_ref(),
+            theirs.settings.as_ref(),
+        ) {
+            Some(settings) => settings.cloned(),
+            None => {
+                conflicts.push(Conflict {
+                    keyword: "igitt".to_string(),
+                    origin: "-".to_string(),
+                    commit: "settings".to_string(),
+                    subject: "the settings".to_string(),
+                    ours: "changed".to_string(),
+                    theirs: "changed differently".to_string(),
+                });
+                ours.settings.clone()
+            }
+        },
+        keywords: Map::new(),
+    };
+
+    let mut seen = HashSet::new();
+    for (keyword, commits) in &ours.keywords {
+        let merged_commits = merged.keywords.entry(keyword.clone()).or_default();
+        for commit in commits {
+            let key = (
+                keyword.clone(),
+                commit.origin.clone(),
+                commit.commit.clone(),
+            );
+            let base_commit = base_commits.get(&key).copied();
+            match theirs_commits.get(&key) {
+                Some(theirs_commit) => merged_commits.push(merge_commit3(
+                    &key,
+                    base_commit,
+                    commit,
+                    theirs_commit,
+                    &mut conflicts,
+                )),
+                // removed by them, which only counts if we left the commit as it was
+                None => match base_commit {
+                    Some(base_commit) if base_commit == commit => {}
+                    Some(_) => {
+                        conflicts.push(Conflict {
+                            keyword: key.0.clone(),
+                            origin: key.1.clone(),
+                            commit: key.2.clone(),
+                            subject: "the commit".to_string(),
+                            ours: "changed".to_string(),
+                            theirs: "removed".to_string(),
+                        });
+                        merged_commits.push(commit.clone());
+                    }
+                    None => merged_commits.push(commit.clone()),
+                },
+            }
+            seen.insert(key);
+        }
+    }
+    for (keyword, commits) in &theirs.keywords {
+        let merged_commits = merged.keywords.entry(keyword.clone()).or_default();
+        for commit in commits {
+            let key = (
+                keyword.clone(),
+                commit.origin.clone(),
+                commit.commit.clone(),
+            );
+            if seen.contains(&key) {
+                continue;
+            }
+            // removed by us, which only counts if they left the commit as it was
+            match base_commits.get(&key) {
+                Some(base_commit) if *base_commit == commit => {}
+                Some(_) => {
+                    conflicts.push(Conflict {
+                        keyword: key.0.clone(),
+                        origin: key.1.clone(),
+                        commit: key.2.clone(),
+                        subject: "the commit".to_string(),
+                        ours: "removed".to_string(),
+                        theirs: "changed".to_string(),
+                    });
+                    merged_commits.push(commit.clone());
+                }
+                None => merged_commits.push(commit.clone()),
+            }
+        }
+    }
+    // keywords removed on one side and left as they were on the other stay removed
+    merged.keywords.retain(|keyword, commits| {
+        !commits.is_empty()
+            || ours.keywords.contains_key(keyword) && theirs.keywords.contains_key(keyword)
+    });
+    (merged, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(yaml: &str) -> Document {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    /// The keyword `kw` with the given commits, which are given as YAML mappings.
+    fn commits(commits: &[&str]) -> Document {
+        if commits.is_empty() {
+            return document("kw: []");
+        }
+        let mut yaml = "kw:\n".to_string();
+        for commit in commits {
+            yaml.push_str(&format!("  - {{ origin: o, {} }}\n", commit));
+        }
+        document(&yaml)
+    }
+
+    fn hashes(document: &Document) -> Vec<&str> {
+        document.keywords["kw"]
+            .iter()
+            .map(|commit| commit.commit.as_str())
+            .collect()
+    }
+
+    const UNRATED: &str = "commit: a";
+    const RATED: &str = "commit: a, rating: { alice: { label: refactoring, comment: '' } }";
+
+    fn rating(label: &str, comment: &str) -> Rating {
+        Rating {
+            label: Some(label.to_string()),
+            comment: comment.to_string(),
+        }
+    }
+
+    #[test]
+    fn ratings_with_different_labels_conflict() {
+        assert_eq!(
+            merge_rating(&rating("refactoring", ""), &rating("no_refactoring", "")),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_comment_is_taken_over() {
+        let commented = rating("refactoring", "renames a module");
+        let plain = rating("refactoring", "");
+        assert_eq!(merge_rating(&plain, &commented), Some(commented.clone()));
+        assert_eq!(merge_rating(&commented, &plain), Some(commented));
+    }
+
+    #[test]
+    fn different_comments_are_joined() {
+        let first = rating("refactoring", "renames a module");
+        let second = rating("refactoring", "moves a method");
+        let merged = merge_rating(&first, &second).unwrap();
+        assert_eq!(merged.comment, "renames a module\n\nmoves a method");
+        // merging again, e.g. with a third file, doesn't repeat the comments
+        assert_eq!(merge_rating(&merged, &second), Some(merged.clone()));
+        assert_eq!(merge_rating(&merged, &first), Some(merged));
+    }
+
+    #[test]
+    fn ratings_of_different_raters_are_combined() {
+        let (merged, conflicts) = merge3(
+            commits(&[UNRATED]),
+            commits(&[RATED]),
+            commits(&["commit: a, rating: { bob: { label: refactoring, comment: '' } }"]),
+        );
+        assert!(conflicts.is_empty());
+        let raters: Vec<&String> = merged.keywords["kw"][0].rating.keys().collect();
+        assert_eq!(raters, vec!["alice", "bob"]);
+    }
+
+    #[test]
+    fn rating_changed_differently_conflicts() {
+        let (merged, conflicts) = merge3(
+            commits(&[UNRATED]),
+            commits(&[RATED]),
+            commits(&["commit: a, rating: { alice: { label: no_refactoring, comment: '' } }"]),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].subject, "the rating of alice");
+        assert_eq!(
+            merged.keywords["kw"][0].rating["alice"].label.as_deref(),
+            Some("refactoring")
+        );
+    }
+
+    #[test]
+    fn removed_commit_which_is_unchanged_stays_removed() {
+        let base = commits(&[UNRATED, "commit: b"]);
+        let (merged, conflicts) = merge3(base.clone(), commits(&[UNRATED]), base.clone());
+        assert!(conflicts.is_empty());
+        assert_eq!(hashes(&merged), vec!["a"]);
+        let (merged, conflicts) = merge3(base.clone(), base, commits(&["commit: b"]));
+        assert!(conflicts.is_empty());
+        assert_eq!(hashes(&merged), vec!["b"]);
+    }
+
+    #[test]
+    fn removed_commit_which_is_changed_conflicts() {
+        let (merged, conflicts) = merge3(commits(&[UNRATED]), commits(&[]), commits(&[RATED]));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            (conflicts[0].ours.as_str(), conflicts[0].theirs.as_str()),
+            ("removed", "changed")
+        );
+        assert!(merged.keywords["kw"][0].rating.contains_key("alice"));
+
+        let (merged, conflicts) = merge3(commits(&[UNRATED]), commits(&[RATED]), commits(&[]));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            (conflicts[0].ours.as_str(), conflicts[0].theirs.as_str()),
+            ("changed", "removed")
+        );
+        assert!(merged.keywords["kw"][0].rating.contains_key("alice"));
+    }
+
+    #[test]
+    fn moved_commit_stays_moved() {
+        // one side marks the commit as moved while the other one rates it
+        let (merged, conflicts) = merge3(
+            commits(&[UNRATED]),
+            commits(&["commit: a, moved: true, moved_reason: gone"]),
+            commits(&[RATED]),
+        );
+        assert!(conflicts.is_empty());
+        let commit = &merged.keywords["kw"][0];
+        assert!(commit.moved);
+        assert_eq!(commit.moved_reason.as_deref(), Some("gone"));
+        assert!(commit.rating.contains_key("alice"));
+
+        // both sides found it to be moved for different reasons
+        let (merged, conflicts) = merge3(
+            commits(&[UNRATED]),
+            commits(&["commit: a, moved: true"]),
+            commits(&["commit: a, moved: true, moved_reason: gone"]),
+        );
+        assert!(conflicts.is_empty());
+        let commit = &merged.keywords["kw"][0];
+        assert!(commit.moved);
+        assert_eq!(commit.moved_reason.as_deref(), Some("gone"));
+    }
+
+    #[test]
+    fn settings_changed_on_one_side_are_taken() {
+        let patterns = document("igitt: { patterns: { kw: [keyword] } }\nkw: []");
+        let (merged, conflicts) = merge3(commits(&[]), commits(&[]), patterns.clone());
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.settings, patterns.settings);
+    }
+
+    #[test]
+    fn settings_changed_differently_conflict() {
+        let ours = document("igitt: { patterns: { kw: [ours] } }\nkw: []");
+        let theirs = document("igitt: { patterns: { kw: [theirs] } }\nkw: []");
+        let (merged, conflicts) = merge3(commits(&[]), ours.clone(), theirs);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].commit, "settings");
+        assert_eq!(merged.settings, ours.settings);
+    }
+}
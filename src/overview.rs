@@ -0,0 +1,194 @@
+use cursive::align::HAlign;
+use cursive::event::Key;
+use cursive::theme::{BaseColor, Color, Style};
+use cursive::utils::span::SpannedString;
+use cursive::view::{Nameable, Resizable, Scrollable};
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, SelectView, TextView};
+use cursive::Cursive;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// State of a commit from the view of the current rater.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    Unrated,
+    Rated,
+    Moved,
+    /// The raters have chosen different labels and no adjudication resolves it.
+    Conflict,
+}
+
+impl Status {
+    const ALL: [Status; 4] = [
+        Status::Unrated,
+        Status::Rated,
+        Status::Moved,
+        Status::Conflict,
+    ];
+
+    fn color(self) -> Color {
+        match self {
+            Status::Unrated => Color::Dark(BaseColor::White),
+            Status::Rated => Color::Dark(BaseColor::Green),
+            Status::Moved => Color::Dark(BaseColor::Yellow),
+            Status::Conflict => Color::Dark(BaseColor::Red),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Unrated => "unrated",
+            Status::Rated => "rated",
+            Status::Moved => "moved",
+            Status::Conflict => "conflict",
+        })
+    }
+}
+
+/// A commit of the walk through the document.
+#[derive(Clone)]
+pub struct Entry {
+    pub keyword: String,
+    pub section: String,
+    pub origin: String,
+    pub commit: String,
+    pub status: Status,
+}
+
+impl Entry {
+    fn matches(&self, filter: &str) -> bool {
+        [&self.keyword, &self.section, &self.origin, &self.commit]
+            .iter()
+            .any(|field| field.to_lowercase().contains(filter))
+    }
+
+    fn label(&self, is_current: bool) -> SpannedString<Style> {
+        let mut label = SpannedString::new();
+        label.append_plain(if is_current { "▶ " } else { "  " });
+        label.append_styled(format!("{:<8}", self.status), self.status.color());
+        label.append_plain(format!(
+            " {} / {} | {} @ {}",
+            self.keyword,
+            self.section,
+            self.origin,
+            &self.commit[..self.commit.len().min(12)]
+        ));
+        label
+    }
+}
+
+/// All commits of the walk along with the position of the shown one, kept up to date by the
+/// main loop.
+#[derive(Default)]
+pub struct Overview {
+    pub entries: Vec<Entry>,
+    pub current: usize,
+}
+
+impl Overview {
+    /// The number of commits the current rater is done with.
+    pub fn progress(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status != Status::Unrated)
+            .count()
+    }
+
+    /// The position of the next commit the current rater hasn't rated yet, continuing from the
+    /// start once the end is reached.
+    pub fn next_unrated(&self) -> Option<usize> {
+        let len = self.entries.len();
+        (1..len)
+            .map(|offset| (self.current + offset) % len)
+            .find(|pos| self.entries[*pos].status == Status::Unrated)
+    }
+}
+
+/// Fills the list with the entries passing the text and status filters.
+fn refresh(siv: &mut Cursive, overview: &Overview) {
+    let filter = siv
+        .find_name::<EditView>("overview_filter")
+        .map(|edit| edit.get_content().to_lowercase())
+        .unwrap_or_default();
+    let status = siv
+        .find_name::<SelectView<Option<Status>>>("overview_status")
+        .and_then(|select| select.selection())
+        .and_then(|status| *status);
+    let mut list = match siv.find_name::<SelectView<usize>>("overview_list") {
+        Some(list) => list,
+        None => return,
+    };
+    list.clear();
+    for (pos, entry) in overview.entries.iter().enumerate() {
+        if (status.is_none() || status == Some(entry.status)) && entry.matches(&filter) {
+            list.add_item(entry.label(pos == overview.current), pos);
+        }
+    }
+    let current = list.iter().position(|(_, pos)| *pos == overview.current);
+    if let Some(idx) = current {
+        list.set_selection(idx);
+    }
+}
+
+/// Lists all commits, the one submitted is passed on to `on_jump`.
+pub fn show<F>(siv: &mut Cursive, overview: &Arc<Mutex<Overview>>, on_jump: F)
+where
+    F: Fn(&mut Cursive, usize) + 'static,
+{
+    // a snapshot suffices, the overview is closed before another commit is shown
+    let snapshot = {
+        let overview = overview.lock().unwrap();
+        Rc::new(Overview {
+            entries: overview.entries.clone(),
+            current: overview.current,
+        })
+    };
+
+    let mut status_select = SelectView::<Option<Status>>::new().popup();
+    status_select.add_item("all", None);
+    for status in Status::ALL.iter() {
+        status_select.add_item(status.to_string(), Some(*status));
+    }
+    let status_snapshot = snapshot.clone();
+    let status_select =
+        status_select.on_submit(move |siv, _: &Option<Status>| refresh(siv, &status_snapshot));
+    let filter_snapshot = snapshot.clone();
+    let filter = EditView::new().on_edit(move |siv, _, _| refresh(siv, &filter_snapshot));
+
+    let mut filters = LinearLayout::horizontal();
+    filters.add_child(TextView::new("Filter: "));
+    filters.add_child(filter.with_name("overview_filter").full_width());
+    filters.add_child(TextView::new(" Status: "));
+    filters.add_child(status_select.with_name("overview_status"));
+
+    let list = SelectView::<usize>::new().on_submit(move |siv, pos: &usize| {
+        siv.pop_layer();
+        on_jump(siv, *pos);
+    });
+    let mut layout = LinearLayout::vertical();
+    layout.add_child(filters);
+    layout.add_child(list.with_name("overview_list").scrollable().full_screen());
+
+    siv.add_layer(
+        OnEventView::new(
+            Dialog::around(layout)
+                .title(format!(
+                    "Overview | {}/{} rated",
+                    snapshot.progress(),
+                    snapshot.entries.len()
+                ))
+                .title_position(HAlign::Left)
+                .button("Close", |siv| {
+                    siv.pop_layer();
+                })
+                .full_screen(),
+        )
+        .on_event(Key::Esc, |siv| {
+            siv.pop_layer();
+        }),
+    );
+    refresh(siv, &snapshot);
+}
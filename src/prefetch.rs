@@ -0,0 +1,179 @@
+use crate::fetch::{Fetched, Fetcher, Unavailable};
+use async_std::task;
+use std::collections::BTreeMap as Map;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Receives the result of a fetch once it is done.
+pub type Slot = Arc<Mutex<Option<Fetched>>>;
+
+/// How often a prefetched commit is fetched again if it could not be retrieved.
+const RETRIES: u32 = 3;
+
+/// Fetches the commits following the current one in the background.
+pub struct Prefetcher {
+    fetcher: Fetcher,
+    /// Origin and hash of every commit in the order they are visited.
+    targets: Vec<(String, String)>,
+    window: usize,
+    slots: Map<usize, Slot>,
+}
+
+impl Prefetcher {
+    pub fn new(fetcher: Fetcher, targets: Vec<(String, String)>, window: usize) -> Self {
+        Prefetcher {
+            fetcher,
+            targets,
+            window,
+            slots: Map::new(),
+        }
+    }
+
+    /// Returns the slot of the commit at `pos` and starts prefetching the commits after it.
+    pub fn get(&mut self, pos: usize) -> Slot {
+        let slot = match self.slots.remove(&pos) {
+            Some(slot) => slot,
+            None => self.spawn(pos, 0),
+        };
+        let upcoming = pos + 1..(pos + 1 + self.window).min(self.targets.len());
+        // commits which left the window are dropped, their fetch still fills the cache
+        self.slots.retain(|idx, _| upcoming.contains(idx));
+        for idx in upcoming {
+            if !self.slots.contains_key(&idx) {
+                let slot = self.spawn(idx, RETRIES);
+                self.slots.insert(idx, slot);
+            }
+        }
+        slot
+    }
+
+    fn spawn(&self, pos: usize, retries: u32) -> Slot {
+        let slot: Slot = Arc::new(Mutex::new(None));
+        let task_slot = slot.clone();
+        let fetcher = self.fetcher.clone();
+        let (origin, hash) = self.targets[pos].clone();
+        task::spawn(async move {
+            let mut result = fetcher.fetch(&origin, &hash).await;
+            for attempt in 0..retries {
+                if result.0.is_ok() && result.1.is_ok() {
+                    break;
+                }
+                task::sleep(Duration::from_secs(1 << attempt)).await;
+                result = fetcher.fetch(&origin, &hash).await;
+            }
+            *task_slot.lock().unwrap() = Some(result);
+        });
+        slot
+    }
+}
+
+/// A commit which could not be retrieved by `download` or `check`.
+pub struct Failure {
+    pub origin: String,
+    pub commit: String,
+    pub error: anyhow::Error,
+}
+
+fn print_progress(done: usize, total: usize) {
+    const WIDTH: usize = 40;
+    let filled = (done * WIDTH).checked_div(total).unwrap_or(WIDTH);
+    eprint!(
+        "\r[{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        done,
+        total
+    );
+    std::io::stderr().flush().ok();
+}
+
+#[derive(Clone, Copy)]
+enum Job {
+    Store,
+    Check,
+}
+
+impl Job {
+    async fn run(self, fetcher: &Fetcher, origin: &str, commit: &str) -> anyhow::Result<()> {
+        match self {
+            Job::Store => fetcher.store(origin, commit).await,
+            Job::Check => {
+                let (message, diff) = fetcher.fetch_uncached(origin, commit).await;
+                // the diff comes first, as errors of the message are only kept as text
+                diff.and(message).map(|_| ())
+            }
+        }
+    }
+}
+
+/// Stores all targets in the cache of the fetcher, with at most `jobs` commits fetched at once.
+pub async fn download(
+    fetcher: Fetcher,
+    targets: Vec<(String, String)>,
+    jobs: usize,
+) -> Vec<Failure> {
+    run(Job::Store, fetcher, targets, jobs).await
+}
+
+/// Fetches all targets to find the ones which are no longer available.
+pub async fn check(fetcher: Fetcher, targets: Vec<(String, String)>, jobs: usize) -> Vec<Failure> {
+    run(Job::Check, fetcher, targets, jobs).await
+}
+
+async fn run(
+    job: Job,
+    fetcher: Fetcher,
+    targets: Vec<(String, String)>,
+    jobs: usize,
+) -> Vec<Failure> {
+    let total = targets.len();
+    let targets = Arc::new(targets);
+    let next = Arc::new(AtomicUsize::new(0));
+    let done = Arc::new(AtomicUsize::new(0));
+    print_progress(0, total);
+    let workers = (0..jobs.max(1))
+        .map(|_| {
+            let fetcher = fetcher.clone();
+            let targets = targets.clone();
+            let next = next.clone();
+            let done = done.clone();
+            task::spawn(async move {
+                let mut failures = Vec::new();
+                loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let (origin, commit) = match targets.get(idx) {
+                        Some(target) => target,
+                        None => break,
+                    };
+                    let mut result = job.run(&fetcher, origin, commit).await;
+                    for attempt in 0..RETRIES {
+                        match &result {
+                            Ok(()) => break,
+                            Err(err) if err.downcast_ref::<Unavailable>().is_some() => break,
+                            Err(_) => {}
+                        }
+                        task::sleep(Duration::from_secs(1 << attempt)).await;
+                        result = job.run(&fetcher, origin, commit).await;
+                    }
+                    if let Err(error) = result {
+                        failures.push(Failure {
+                            origin: origin.clone(),
+                            commit: commit.clone(),
+                            error,
+                        });
+                    }
+                    print_progress(done.fetch_add(1, Ordering::SeqCst) + 1, total);
+                }
+                failures
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut failures = Vec::new();
+    for worker in workers {
+        failures.extend(worker.await);
+    }
+    eprintln!();
+    failures
+}
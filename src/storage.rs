@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Adds a prefix and a suffix to the file name of the path.
+fn sibling(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(prefix);
+    name.push(path.file_name().unwrap_or_default());
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// The file the progress of a session on the document is recorded in, `.#<file>.igitt` next to
+/// the document.
+pub fn recovery_path(path: &Path) -> PathBuf {
+    sibling(path, ".#", ".igitt")
+}
+
+/// The previous version of the document, kept as `<file>.bak` whenever it is saved.
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, "", ".bak")
+}
+
+/// Replaces the file with the given contents, so that it either holds the old or the new contents
+/// even if igitt or the system crashes meanwhile.
+fn replace(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp_path = sibling(path, ".", ".tmp");
+    let mut tmp = File::create(&tmp_path)
+        .with_context(|| format!("could not create {}", tmp_path.display()))?;
+    tmp.write_all(contents)
+        .and_then(|_| tmp.sync_all())
+        .with_context(|| format!("could not write {}", tmp_path.display()))?;
+    drop(tmp);
+    fs::rename(&tmp_path, path).with_context(|| format!("could not replace {}", path.display()))?;
+    // the rename itself is only durable once the directory has been synced
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .with_context(|| format!("could not sync {}", dir.display()))?;
+    Ok(())
+}
+
+/// Atomically writes the contents to the path.
+pub fn write(path: &Path, contents: &str) -> Result<()> {
+    replace(path, contents.as_bytes())
+}
+
+/// Atomically writes the contents to the path, the previous version is kept as backup.
+pub fn save(path: &Path, contents: &str) -> Result<()> {
+    if path.exists() {
+        let previous =
+            fs::read(path).with_context(|| format!("could not read {}", path.display()))?;
+        replace(&backup_path(path), &previous)?;
+    }
+    write(path, contents)
+}
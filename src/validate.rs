@@ -0,0 +1,848 @@
+use crate::forge::{Forges, Repository};
+use crate::local;
+use regex::Regex;
+use std::collections::BTreeMap as Map;
+use std::fmt;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle};
+use yaml_rust::Yaml;
+
+/// A problem found in a keywords file.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A YAML node along with the line it starts on, which serde_yaml doesn't keep.
+#[derive(Debug, Clone)]
+struct Node {
+    line: usize,
+    value: Value,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(String, TScalarStyle),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Node, Node)>),
+}
+
+enum Partial {
+    Sequence(Marker, usize, Vec<Node>),
+    Mapping(Marker, usize, Vec<(Node, Node)>, Option<Node>),
+}
+
+/// Builds the node tree of the first document from the events of the parser.
+#[derive(Default)]
+struct Builder {
+    stack: Vec<Partial>,
+    anchors: Map<usize, Node>,
+    root: Option<Node>,
+}
+
+impl Builder {
+    fn complete(&mut self, mut node: Node, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        match self.stack.last_mut() {
+            Some(Partial::Sequence(_, _, items)) => items.push(node),
+            Some(Partial::Mapping(_, _, entries, key)) => match key.take() {
+                Some(key) => {
+                    // a missing value is marked at the token following it
+                    if node.kind() == "empty" {
+                        node.line = node.line.min(key.line);
+                    }
+                    entries.push((key, node))
+                }
+                None => *key = Some(node),
+            },
+            None => {
+                if self.root.is_none() {
+                    self.root = Some(node);
+                }
+            }
+        }
+    }
+}
+
+impl MarkedEventReceiver for Builder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::Scalar(value, style, anchor, _) => self.complete(
+                Node {
+                    line: mark.line(),
+                    value: Value::Scalar(value, style),
+                },
+                anchor,
+            ),
+            Event::Alias(anchor) => {
+                let node = self.anchors.get(&anchor).cloned().unwrap_or(Node {
+                    line: mark.line(),
+                    value: Value::Scalar(String::new(), TScalarStyle::Plain),
+                });
+                self.complete(node, 0);
+            }
+            Event::SequenceStart(anchor) => {
+                self.stack.push(Partial::Sequence(mark, anchor, Vec::new()))
+            }
+            Event::MappingStart(anchor) => {
+                self.stack
+                    .push(Partial::Mapping(mark, anchor, Vec::new(), None))
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let (node, anchor) = match self.stack.pop() {
+                    Some(Partial::Sequence(mark, anchor, items)) => (
+                        Node {
+                            line: mark.line(),
+                            value: Value::Sequence(items),
+                        },
+                        anchor,
+                    ),
+                    Some(Partial::Mapping(mark, anchor, entries, _)) => (
+                        Node {
+                            line: mark.line(),
+                            value: Value::Mapping(entries),
+                        },
+                        anchor,
+                    ),
+                    None => return,
+                };
+                self.complete(node, anchor);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Node {
+    /// The string of a scalar, plain scalars which YAML reads as numbers, booleans or null are
+    /// not strings.
+    fn string(&self) -> Option<&str> {
+        match &self.value {
+            Value::Scalar(value, TScalarStyle::Plain) => match Yaml::from_str(value) {
+                Yaml::String(_) => Some(value),
+                _ => None,
+            },
+            Value::Scalar(value, _) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn boolean(&self) -> Option<bool> {
+        match &self.value {
+            Value::Scalar(value, TScalarStyle::Plain) => Yaml::from_str(value).as_bool(),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match &self.value {
+            Value::Scalar(value, TScalarStyle::Plain) => match Yaml::from_str(value) {
+                Yaml::Integer(_) | Yaml::Real(_) => "a number",
+                Yaml::Boolean(_) => "a boolean",
+                Yaml::Null => "empty",
+                _ => "a string",
+            },
+            Value::Scalar(..) => "a string",
+            Value::Sequence(_) => "a list",
+            Value::Mapping(_) => "a mapping",
+        }
+    }
+}
+
+struct Validator<'a> {
+    forges: &'a Forges,
+    problems: Vec<Problem>,
+    /// The labels declared in the settings.
+    labels: Vec<String>,
+    /// Every commit by origin and hash, along with its keyword and line.
+    commits: Map<(String, String), (String, usize)>,
+    /// Every spelling of a rater name by its normalized form, along with its first line.
+    raters: Map<String, Vec<(String, usize)>>,
+    hash_re: Regex,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, line: usize, message: String) {
+        self.problems.push(Problem { line, message });
+    }
+
+    /// The fields of a mapping, unknown and missing fields are reported.
+    fn fields<'n>(
+        &mut self,
+        node: &'n Node,
+        what: &str,
+        known: &[&str],
+        required: &[&str],
+    ) -> Option<Map<&'n str, &'n Node>> {
+        let entries = match &node.value {
+            Value::Mapping(entries) => entries,
+            _ => {
+                self.report(
+                    node.line,
+                    format!("{} has to be a mapping, but is {}", what, node.kind()),
+                );
+                return None;
+            }
+        };
+        let mut fields = Map::new();
+        for (key, value) in entries {
+            match key.string() {
+                Some(name) if known.contains(&name) => {
+                    if fields.insert(name, value).is_some() {
+                        self.report(
+                            key.line,
+                            format!("field '{}' of {} is repeated", name, what),
+                        );
+                    }
+                }
+                _ => self.report(
+                    key.line,
+                    format!(
+                        "unknown field '{}' in {}, expected one of {}",
+                        scalar(key),
+                        what,
+                        known.join(", ")
+                    ),
+                ),
+            }
+        }
+        for name in required {
+            if !fields.contains_key(name) {
+                self.report(node.line, format!("{} lacks the field '{}'", what, name));
+            }
+        }
+        Some(fields)
+    }
+
+    fn string<'n>(&mut self, node: &'n Node, what: &str) -> Option<&'n str> {
+        let string = node.string();
+        if string.is_none() {
+            let hint = match &node.value {
+                Value::Scalar(..) => ", quote it",
+                _ => "",
+            };
+            self.report(
+                node.line,
+                format!(
+                    "{} has to be a string, but is {}{}",
+                    what,
+                    node.kind(),
+                    hint
+                ),
+            );
+        }
+        string
+    }
+
+    fn boolean(&mut self, node: &Node, what: &str) {
+        if node.boolean().is_none() {
+            self.report(
+                node.line,
+                format!("{} has to be true or false, but is {}", what, node.kind()),
+            );
+        }
+    }
+
+    fn settings(&mut self, node: &Node) {
+        let fields = match self.fields(node, "the settings", &["labels", "patterns"], &[]) {
+            Some(fields) => fields,
+            None => return,
+        };
+        if let Some(labels) = fields.get("labels") {
+            self.label_schema(labels);
+        }
+        if let Some(patterns) = fields.get("patterns") {
+            let entries = match &patterns.value {
+                Value::Mapping(entries) => entries,
+                _ => {
+                    let kind = patterns.kind();
+                    return self.report(
+                        patterns.line,
+                        format!("the patterns have to be a mapping, but are {}", kind),
+                    );
+                }
+            };
+            for (keyword, patterns) in entries {
+                let keyword = scalar(keyword);
+                let what = format!("the patterns of keyword '{}'", keyword);
+                for pattern in self.sequence(patterns, &what) {
+                    let what = format!("a pattern of keyword '{}'", keyword);
+                    if let Some(source) = self.string(pattern, &what) {
+                        if let Err(err) = Regex::new(source) {
+                            // the error spans several lines, ending with the cause
+                            let err = err.to_string();
+                            let cause = err.lines().last().unwrap_or_default();
+                            let cause = cause.trim_start_matches("error: ");
+                            self.report(
+                                pattern.line,
+                                format!("invalid pattern '{}': {}", source, cause),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn label_schema(&mut self, node: &Node) {
+        let mut keys = Map::new();
+        let mut labels = Vec::new();
+        for label in self.sequence(node, "the labels") {
+            let fields = match self.fields(
+                label,
+                "a label",
+                &["id", "description", "key", "positive"],
+                &["id", "description"],
+            ) {
+                Some(fields) => fields,
+                None => continue,
+            };
+            if let Some(id) = fields.get("id") {
+                if let Some(name) = self.string(id, "the label id") {
+                    if name.is_empty() {
+                        self.report(id.line, "label ids must not be empty".to_string());
+                    } else if labels.iter().any(|existing| existing == name) {
+                        self.report(
+                            id.line,
+                            format!("label '{}' is declared more than once", name),
+                        );
+                    } else {
+                        labels.push(name.to_string());
+                    }
+                }
+            }
+            if let Some(description) = fields.get("description") {
+                self.string(description, "the label description");
+            }
+            if let Some(key) = fields.get("key") {
+                match key.string().or_else(|| scalar_str(key)) {
+                    Some(value) if value.chars().count() == 1 => {
+                        if let Some(line) = keys.insert(value.to_string(), key.line) {
+                            self.report(
+                                key.line,
+                                format!(
+                                    "key '{}' is already assigned to the label at line {}",
+                                    value, line
+                                ),
+                            );
+                        }
+                    }
+                    _ => self.report(
+                        key.line,
+                        format!(
+                            "the key of a label has to be a single character, but is '{}'",
+                            scalar(key)
+                        ),
+                    ),
+                }
+            }
+            if let Some(positive) = fields.get("positive") {
+                self.boolean(positive, "whether a label is positive");
+            }
+        }
+        if labels.is_empty() {
+            self.report(
+                node.line,
+                "the label schema has to contain at least one label".to_string(),
+            );
+        }
+        self.labels = labels;
+    }
+
+    fn sequence<'n>(&mut self, node: &'n Node, what: &str) -> &'n [Node] {
+        match &node.value {
+            Value::Sequence(items) => items,
+            _ => {
+                self.report(
+                    node.line,
+                    format!("{} have to be a list, but are {}", what, node.kind()),
+                );
+                &[]
+            }
+        }
+    }
+
+    fn label(&mut self, node: &Node, what: &str) {
+        if let Some(label) = self.string(node, what) {
+            if !self.labels.iter().any(|existing| existing == label) {
+                let message = format!(
+                    "{} '{}' is not declared, expected one of {}",
+                    what,
+                    label,
+                    self.labels.join(", ")
+                );
+                self.report(node.line, message);
+            }
+        }
+    }
+
+    /// Records the spelling of a rater name, spellings which only differ in case or surrounding
+    /// whitespace most likely refer to the same rater.
+    fn rater(&mut self, name: &str, line: usize) {
+        if name.trim().is_empty() {
+            return self.report(line, "rater names must not be empty".to_string());
+        }
+        let spellings = self.raters.entry(name.trim().to_lowercase()).or_default();
+        if !spellings.iter().any(|(spelling, _)| spelling == name) {
+            spellings.push((name.to_string(), line));
+        }
+    }
+
+    fn origin(&mut self, node: &Node, origin: &str) {
+        if let Some(path) = local::repository_path(origin) {
+            if !path.exists() {
+                self.report(
+                    node.line,
+                    format!(
+                        "origin '{}' is neither a url nor an existing local repository",
+                        origin
+                    ),
+                );
+            }
+            return;
+        }
+        match Repository::parse(origin) {
+            Ok(repository) if !self.forges.supports(&repository.host) => self.report(
+                node.line,
+                format!(
+                    "no forge is known for host '{}', it has to be declared under 'forges' in the config",
+                    repository.host
+                ),
+            ),
+            Ok(_) => {}
+            Err(_) => self.report(node.line, format!("could not parse origin '{}'", origin)),
+        }
+    }
+
+    fn commit(&mut self, keyword: &str, node: &Node) {
+        let fields = match self.fields(
+            node,
+            "a commit",
+            &[
+                "origin",
+                "moved",
+                "moved_reason",
+                "commit",
+                "section",
+                "time",
+                "rating",
+                "adjudication",
+            ],
+            &["origin", "commit"],
+        ) {
+            Some(fields) => fields,
+            None => return,
+        };
+        let origin = fields
+            .get("origin")
+            .and_then(|origin| Some((*origin, self.string(origin, "the origin")?)));
+        if let Some((node, origin)) = origin {
+            self.origin(node, origin);
+        }
+        let hash = fields
+            .get("commit")
+            .and_then(|hash| Some((*hash, self.string(hash, "the commit hash")?)));
+        if let Some((node, hash)) = hash {
+            if !self.hash_re.is_match(hash) {
+                self.report(
+                    node.line,
+                    format!(
+                        "'{}' is not a full commit hash of 40 lowercase hexadecimal digits",
+                        hash
+                    ),
+                );
+            }
+        }
+        if let (Some((_, origin)), Some((_, hash))) = (origin, hash) {
+            let key = (origin.to_string(), hash.to_string());
+            match self.commits.get(&key) {
+                Some((other, line)) => {
+                    let message = if other == keyword {
+                        format!(
+                            "commit {} of {} is listed twice, first at line {}",
+                            hash, origin, line
+                        )
+                    } else {
+                        format!(
+                            "commit {} of {} is also listed under keyword '{}' at line {}",
+                            hash, origin, other, line
+                        )
+                    };
+                    self.report(node.line, message);
+                }
+                None => {
+                    self.commits.insert(key, (keyword.to_string(), node.line));
+                }
+            }
+        }
+        if let Some(moved) = fields.get("moved") {
+            self.boolean(moved, "whether the commit has moved");
+        }
+        for name in &["moved_reason", "section", "time"] {
+            if let Some(value) = fields.get(name) {
+                self.string(value, &format!("the field '{}'", name));
+            }
+        }
+        if let Some(rating) = fields.get("rating") {
+            self.rating(rating);
+        }
+        if let Some(adjudication) = fields.get("adjudication") {
+            let fields = self.fields(
+                adjudication,
+                "the adjudication",
+                &["adjudicator", "label", "comment"],
+                &["adjudicator", "label", "comment"],
+            );
+            if let Some(fields) = fields {
+                if let Some(adjudicator) = fields.get("adjudicator") {
+                    if let Some(name) = self.string(adjudicator, "the adjudicator") {
+                        self.rater(name, adjudicator.line);
+                    }
+                }
+                if let Some(label) = fields.get("label") {
+                    self.label(label, "the adjudicated label");
+                }
+                if let Some(comment) = fields.get("comment") {
+                    self.string(comment, "the comment of the adjudication");
+                }
+            }
+        }
+    }
+
+    fn rating(&mut self, node: &Node) {
+        let entries = match &node.value {
+            Value::Mapping(entries) => entries,
+            // an empty rating
+            Value::Scalar(..) if node.kind() == "empty" => return,
+            _ => {
+                let kind = node.kind();
+                return self.report(
+                    node.line,
+                    format!(
+                        "the rating has to map raters to their ratings, but is {}",
+                        kind
+                    ),
+                );
+            }
+        };
+        for (rater, rating) in entries {
+            let name = match self.string(rater, "the rater name") {
+                Some(name) => name,
+                None => continue,
+            };
+            self.rater(name, rater.line);
+            let what = format!("the rating of {}", name);
+            let fields = match self.fields(
+                rating,
+                &what,
+                &["label", "is_refactoring", "comment"],
+                &["comment"],
+            ) {
+                Some(fields) => fields,
+                None => continue,
+            };
+            if let Some(label) = fields.get("label") {
+                self.label(label, "the label");
+            }
+            if let Some(is_refactoring) = fields.get("is_refactoring") {
+                self.boolean(is_refactoring, "whether the commit is a refactoring");
+            }
+            if let Some(comment) = fields.get("comment") {
+                self.string(comment, &format!("the comment of {}", name));
+            }
+        }
+    }
+}
+
+/// The text of a scalar, or a description of other nodes.
+fn scalar(node: &Node) -> String {
+    scalar_str(node)
+        .map(str::to_string)
+        .unwrap_or_else(|| node.kind().to_string())
+}
+
+fn scalar_str(node: &Node) -> Option<&str> {
+    match &node.value {
+        Value::Scalar(value, _) => Some(value),
+        _ => None,
+    }
+}
+
+/// Checks a keywords file, all problems are returned along with their line.
+pub fn validate(content: &str, forges: &Forges) -> Vec<Problem> {
+    let mut builder = Builder::default();
+    if let Err(err) = Parser::new(content.chars()).load(&mut builder, false) {
+        let message = err.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(idx) => message[..idx].to_string(),
+            None => message,
+        };
+        return vec![Problem {
+            line: err.marker().line(),
+            message: format!("invalid YAML: {}", message),
+        }];
+    }
+    let root = match builder.root {
+        Some(root) => root,
+        None => {
+            return vec![Problem {
+                line: 1,
+                message: "the file is empty".to_string(),
+            }]
+        }
+    };
+
+    let mut validator = Validator {
+        forges,
+        problems: Vec::new(),
+        labels: vec![
+            crate::labels::REFACTORING.to_string(),
+            crate::labels::NO_REFACTORING.to_string(),
+        ],
+        commits: Map::new(),
+        raters: Map::new(),
+        hash_re: Regex::new("^[0-9a-f]{40}$").unwrap(),
+    };
+    let entries = match &root.value {
+        Value::Mapping(entries) => entries,
+        _ => {
+            return vec![Problem {
+                line: root.line,
+                message: format!(
+                    "the file has to map keywords to their commits, but is {}",
+                    root.kind()
+                ),
+            }]
+        }
+    };
+    // the labels have to be known before the ratings are checked
+    for (key, value) in entries {
+        if key.string() == Some("igitt") {
+            validator.settings(value);
+        }
+    }
+    let mut keywords = Map::new();
+    for (key, value) in entries {
+        let keyword = match validator.string(key, "the keyword") {
+            Some("igitt") => continue,
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if let Some(line) = keywords.insert(keyword, key.line) {
+            validator.report(
+                key.line,
+                format!("keyword '{}' is repeated, first at line {}", keyword, line),
+            );
+        }
+        let what = format!("the commits of keyword '{}'", keyword);
+        for commit in validator.sequence(value, &what) {
+            validator.commit(keyword, commit);
+        }
+    }
+    for spellings in validator.raters.values() {
+        if let [(first, first_line), others @ ..] = spellings.as_slice() {
+            for (spelling, line) in others {
+                validator.problems.push(Problem {
+                    line: *line,
+                    message: format!(
+                        "rater '{}' is spelled '{}' at line {}, both most likely are the same rater",
+                        spelling, first, first_line
+                    ),
+                });
+            }
+        }
+    }
+
+    // anything not covered above still has to be readable by igitt
+    if validator.problems.is_empty() {
+        if let Err(err) = serde_yaml::from_str::<crate::Document>(content) {
+            validator.problems.push(Problem {
+                line: err.location().map_or(1, |location| location.line()),
+                message: err.to_string(),
+            });
+        }
+    }
+    validator.problems.sort_by_key(|problem| problem.line);
+    validator.problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn problems(content: &str) -> Vec<(usize, String)> {
+        let forges = Forges::new(&Map::new(), None, None);
+        validate(content, &forges)
+            .into_iter()
+            .map(|problem| (problem.line, problem.message))
+            .collect()
+    }
+
+    fn commit(hash: &str, rating: &str) -> String {
+        format!(
+            "  - origin: \"https://github.com/jwuensche/igitt\"\n    moved: false\n    commit: \"{}\"\n    rating:{}\n",
+            hash, rating
+        )
+    }
+
+    #[test]
+    fn clean_document() {
+        let content = format!(
+            "igitt:\n  labels:\n    - id: \"yes\"\n      description: \"Refactoring\"\n      key: \"y\"\n      positive: true\n    - id: \"no\"\n      description: \"No refactoring\"\n  patterns:\n    rename:\n      - \"renam(e|ing)\"\nrename:\n{}",
+            commit(
+                HASH,
+                "\n      \"alice\":\n        label: \"yes\"\n        comment: \"\"\n    adjudication:\n      adjudicator: \"bob\"\n      label: \"no\"\n      comment: \"moves only\""
+            )
+        );
+        assert_eq!(problems(&content), vec![]);
+    }
+
+    #[test]
+    fn problems_are_reported_at_their_line() {
+        let content = "\
+rename:
+  - origin: \"https://github.com/jwuensche/igitt\"
+    moved: maybe
+    commit: 0123abc
+    section: 2020
+    rating:
+      \"alice\":
+        label: \"unknown\"
+        comment: \"\"
+  - moved: false
+";
+        assert_eq!(
+            problems(content),
+            vec![
+                (
+                    3,
+                    "whether the commit has moved has to be true or false, but is a string"
+                        .to_string()
+                ),
+                (
+                    4,
+                    "'0123abc' is not a full commit hash of 40 lowercase hexadecimal digits"
+                        .to_string()
+                ),
+                (
+                    5,
+                    "the field 'section' has to be a string, but is a number, quote it".to_string()
+                ),
+                (
+                    8,
+                    "the label 'unknown' is not declared, expected one of refactoring, no_refactoring"
+                        .to_string()
+                ),
+                (10, "a commit lacks the field 'origin'".to_string()),
+                (10, "a commit lacks the field 'commit'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_yaml() {
+        assert_eq!(
+            problems("rename:\n  - origin: \"a\n"),
+            vec![(
+                2,
+                "invalid YAML: while scanning a quoted scalar, found unexpected end of stream"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let content = format!("rename:\n{}    ratings: {{}}\n", commit(HASH, " {}"));
+        assert_eq!(
+            problems(&content),
+            vec![(
+                6,
+                "unknown field 'ratings' in a commit, expected one of origin, moved, moved_reason, commit, section, time, rating, adjudication"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn duplicate_commits() {
+        let content = format!(
+            "rename:\n{}{}move:\n{}",
+            commit(HASH, " {}"),
+            commit(HASH, " {}"),
+            commit(HASH, " {}")
+        );
+        assert_eq!(
+            problems(&content),
+            vec![
+                (
+                    6,
+                    format!(
+                        "commit {} of https://github.com/jwuensche/igitt is listed twice, first at line 2",
+                        HASH
+                    )
+                ),
+                (
+                    11,
+                    format!(
+                        "commit {} of https://github.com/jwuensche/igitt is also listed under keyword 'rename' at line 2",
+                        HASH
+                    )
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn keywords_without_commits() {
+        assert_eq!(problems("rename: []\n"), vec![]);
+        // igitt can't read a keyword without a list of commits
+        assert_eq!(
+            problems("rename: []\nmove:\nrename: []\n"),
+            vec![
+                (
+                    2,
+                    "the commits of keyword 'move' have to be a list, but are empty".to_string()
+                ),
+                (
+                    3,
+                    "keyword 'rename' is repeated, first at line 1".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn spellings_of_a_rater() {
+        let rating = |name: &str| {
+            format!(
+                "\n      \"{}\":\n        label: \"refactoring\"\n        comment: \"\"",
+                name
+            )
+        };
+        let content = format!(
+            "rename:\n{}{}",
+            commit(HASH, &rating("Alice")),
+            commit(&HASH.replace('0', "1"), &rating(" alice"))
+        );
+        assert_eq!(
+            problems(&content),
+            vec![(
+                13,
+                "rater ' alice' is spelled 'Alice' at line 6, both most likely are the same rater"
+                    .to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use crate::diff;
+use crate::highlight;
+use cursive::align::HAlign;
+use cursive::event::Key;
+use cursive::theme::{BaseColor, Color, Effect, Style, Theme};
+use cursive::utils::lines::spans::LinesIterator;
+use cursive::utils::span::SpannedString;
+use cursive::view::{Nameable, Resizable, Scrollable};
+use cursive::views::{
+    Dialog, EditView, NamedView, OnEventView, Panel, ScrollView, SelectView, TextView,
+};
+use cursive::Cursive;
+use regex::Regex;
+
+/// Width of the file list next to the diff, including its borders.
+pub const FILE_LIST_WIDTH: usize = 36;
+
+/// The panel holding the diff, its title shows the state of the search.
+pub type DiffPanel = Panel<NamedView<ScrollView<NamedView<TextView>>>>;
+
+/// State of the commit view, which is shared with the global callbacks as user data.
+#[derive(Default)]
+pub struct ViewState {
+    /// The commit message of the shown commit with its keyword highlighted.
+    message: SpannedString<Style>,
+    /// The files changed by the shown commit, if its diff could be fetched.
+    files: Option<Vec<diff::File>>,
+    collapsed: Vec<bool>,
+    /// The line of the rendered diff every file starts at.
+    offsets: Vec<usize>,
+    side_by_side: bool,
+    search: Option<Search>,
+}
+
+/// A search within the message and the diff of the shown commit.
+struct Search {
+    query: String,
+    pattern: Regex,
+    /// Positions of the matches in the message, which is wrapped, and lines of the matches in the
+    /// rendered diff.
+    message_matches: Vec<usize>,
+    diff_lines: Vec<usize>,
+    /// Index of the match shown last, counted over the message and the diff.
+    current: usize,
+}
+
+impl Search {
+    fn total(&self) -> usize {
+        self.message_matches.len() + self.diff_lines.len()
+    }
+}
+
+/// Emphasizes the matches of a search and returns the positions they start at.
+fn apply_search(
+    text: SpannedString<Style>,
+    search: Option<&Search>,
+) -> (SpannedString<Style>, Vec<usize>) {
+    let search = match search {
+        Some(search) => search,
+        None => return (text, Vec::new()),
+    };
+    let ranges = highlight::find(text.source(), std::slice::from_ref(&search.pattern));
+    let positions = ranges.iter().map(|range| range.start).collect();
+    let text = highlight::emphasize(&text, &ranges, Style::from(Effect::Reverse));
+    (text, positions)
+}
+
+/// The row a position of the text is shown in once the text is wrapped to the given width, as
+/// done by `TextView`.
+fn wrapped_row(text: &SpannedString<Style>, width: usize, position: usize) -> usize {
+    LinesIterator::new(text, width.max(1))
+        .enumerate()
+        .filter_map(|(row, line)| Some((row, line.overall_indices(text.spans_raw())?.0)))
+        .take_while(|(_, start)| *start <= position)
+        .last()
+        .map_or(0, |(row, _)| row)
+}
+
+/// The screen and the settings the diff is rendered for.
+pub struct DiffFormat {
+    width: usize,
+    theme: Theme,
+    side_by_side: bool,
+}
+
+impl DiffFormat {
+    pub fn of(siv: &mut Cursive) -> Self {
+        DiffFormat {
+            width: siv.screen_size().x,
+            theme: siv.current_theme().clone(),
+            side_by_side: siv
+                .with_user_data(|state: &mut ViewState| state.side_by_side)
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Files generated by tools are collapsed when a commit is shown.
+pub fn initially_collapsed(files: &[diff::File]) -> Vec<bool> {
+    files.iter().map(diff::File::is_generated).collect()
+}
+
+/// Renders a diff, side by side if it is enabled and the screen is wide enough, together with the
+/// entries of the file list.
+pub fn render_diff(
+    format: &DiffFormat,
+    files: &[diff::File],
+    collapsed: &[bool],
+    has_file_list: bool,
+) -> (diff::Rendered, Vec<(SpannedString<Style>, usize)>) {
+    let rendered = if format.side_by_side && format.width >= diff::SIDE_BY_SIDE_MIN_WIDTH {
+        // the borders of the panels and the scrollbar take up some columns
+        let mut available = format.width.saturating_sub(8);
+        if has_file_list {
+            available = available.saturating_sub(FILE_LIST_WIDTH);
+        }
+        diff::side_by_side(files, collapsed, available)
+    } else {
+        diff::unified(files, collapsed, &format.theme)
+    };
+    let labels = files
+        .iter()
+        .zip(collapsed)
+        .enumerate()
+        .filter(|(_, (file, _))| !file.path.is_empty())
+        .map(|(idx, (file, collapsed))| (file_label(file, *collapsed), idx))
+        .collect();
+    (rendered, labels)
+}
+
+/// Shows a freshly loaded commit, a search of the previous commit is dropped.
+pub fn show_commit(
+    siv: &mut Cursive,
+    message: SpannedString<Style>,
+    files: Option<Vec<diff::File>>,
+) {
+    siv.with_user_data(|state: &mut ViewState| {
+        state.message = message;
+        state.collapsed = files.as_deref().map_or(Vec::new(), initially_collapsed);
+        state.files = files;
+        state.search = None;
+    });
+    show_message(siv);
+    show_diff(siv);
+    show_search_state(siv);
+}
+
+fn show_message(siv: &mut Cursive) {
+    let content = siv.with_user_data(|state: &mut ViewState| {
+        let (content, positions) = apply_search(state.message.clone(), state.search.as_ref());
+        if let Some(search) = state.search.as_mut() {
+            search.message_matches = positions;
+        }
+        content
+    });
+    if let (Some(content), Some(mut view)) = (content, siv.find_name::<TextView>("message_field")) {
+        view.set_content(content);
+    }
+}
+
+/// The entry of a file in the file list, long paths are shortened from the left.
+fn file_label(file: &diff::File, collapsed: bool) -> SpannedString<Style> {
+    let counts = format!(" +{} -{}", file.added(), file.removed());
+    let room = FILE_LIST_WIDTH.saturating_sub(counts.len() + 6);
+    let chars: Vec<char> = file.path.chars().collect();
+    let path = if chars.len() > room {
+        format!(
+            "…{}",
+            chars[chars.len() - room + 1..].iter().collect::<String>()
+        )
+    } else {
+        file.path.clone()
+    };
+    let mut label = SpannedString::new();
+    label.append_plain(format!("{} {}", if collapsed { "▸" } else { "▾" }, path));
+    label.append_styled(format!(" +{}", file.added()), Color::Dark(BaseColor::Green));
+    label.append_styled(format!(" -{}", file.removed()), Color::Dark(BaseColor::Red));
+    label
+}
+
+/// Renders the diff of the shown commit into the diff panel and the file list.
+fn show_diff(siv: &mut Cursive) {
+    let format = DiffFormat::of(siv);
+    let has_file_list = siv.find_name::<SelectView<usize>>("file_list").is_some();
+    let rendered = siv
+        .with_user_data(|state: &mut ViewState| {
+            let files = state.files.as_ref()?;
+            let (rendered, labels) = render_diff(&format, files, &state.collapsed, has_file_list);
+            state.offsets = rendered.offsets;
+            let (content, positions) = apply_search(rendered.text, state.search.as_ref());
+            if let Some(search) = state.search.as_mut() {
+                // the diff isn't wrapped, so its lines are its rows
+                search.diff_lines = positions
+                    .iter()
+                    .map(|position| content.source()[..*position].matches('\n').count())
+                    .collect();
+            }
+            Some((content, labels))
+        })
+        .flatten();
+    let (content, labels) = match rendered {
+        Some(rendered) => rendered,
+        None => return,
+    };
+    if let Some(mut view) = siv.find_name::<TextView>("diff_field") {
+        view.set_content(content);
+    }
+    if let Some(mut file_list) = siv.find_name::<SelectView<usize>>("file_list") {
+        let selected = file_list.selected_id();
+        file_list.clear();
+        for (label, idx) in labels {
+            file_list.add_item(label, idx);
+        }
+        if let Some(selected) = selected {
+            file_list.set_selection(selected);
+        }
+    }
+}
+
+pub fn toggle_side_by_side(siv: &mut Cursive) {
+    siv.with_user_data(|state: &mut ViewState| state.side_by_side = !state.side_by_side);
+    show_diff(siv);
+    show_search_state(siv);
+}
+
+/// Scrolls the diff to the start of a file.
+pub fn jump_to_file(siv: &mut Cursive, idx: usize) {
+    let offset = siv
+        .with_user_data(|state: &mut ViewState| state.offsets.get(idx).copied())
+        .flatten();
+    if let Some(offset) = offset {
+        scroll_to(siv, "diff_scroll", offset);
+    }
+}
+
+fn scroll_to(siv: &mut Cursive, name: &str, line: usize) {
+    if let Some(mut scroll) = siv.find_name::<ScrollView<NamedView<TextView>>>(name) {
+        scroll.set_offset((0, line));
+    }
+}
+
+/// Collapses the file selected in the file list, or expands it again.
+pub fn toggle_collapsed(siv: &mut Cursive) {
+    let selected = siv
+        .find_name::<SelectView<usize>>("file_list")
+        .and_then(|file_list| file_list.selection())
+        .map(|idx| *idx);
+    let idx = match selected {
+        Some(idx) => idx,
+        None => return,
+    };
+    siv.with_user_data(|state: &mut ViewState| {
+        if let Some(collapsed) = state.collapsed.get_mut(idx) {
+            *collapsed = !*collapsed;
+        }
+    });
+    show_diff(siv);
+    show_search_state(siv);
+    // the new content is laid out before the next callback, so the offset is valid by then
+    siv.cb_sink()
+        .send(Box::new(move |siv| jump_to_file(siv, idx)))
+        .unwrap();
+}
+
+/// Asks for a text to search for in the message and the diff.
+pub fn search_prompt(siv: &mut Cursive) {
+    siv.add_layer(
+        OnEventView::new(
+            Dialog::around(
+                EditView::new()
+                    .on_submit(|siv, query| {
+                        siv.pop_layer();
+                        start_search(siv, query);
+                    })
+                    .fixed_width(40),
+            )
+            .title("Search"),
+        )
+        .on_event(Key::Esc, |siv| {
+            siv.pop_layer();
+        }),
+    );
+}
+
+fn start_search(siv: &mut Cursive, query: &str) {
+    if query.is_empty() {
+        clear_search(siv);
+        return;
+    }
+    let pattern = match Regex::new(&format!("(?i){}", regex::escape(query))) {
+        Ok(pattern) => pattern,
+        Err(_) => return,
+    };
+    siv.with_user_data(|state: &mut ViewState| {
+        state.search = Some(Search {
+            query: query.to_string(),
+            pattern,
+            message_matches: Vec::new(),
+            diff_lines: Vec::new(),
+            current: 0,
+        })
+    });
+    show_message(siv);
+    show_diff(siv);
+    show_current_match(siv);
+}
+
+pub fn clear_search(siv: &mut Cursive) {
+    let active = siv
+        .with_user_data(|state: &mut ViewState| state.search.take().is_some())
+        .unwrap_or(false);
+    if active {
+        show_message(siv);
+        show_diff(siv);
+        show_search_state(siv);
+    }
+}
+
+/// Moves to the next or previous match, returns false if no search is active.
+pub fn jump_to_match(siv: &mut Cursive, forward: bool) -> bool {
+    let active = siv
+        .with_user_data(|state: &mut ViewState| match state.search.as_mut() {
+            Some(search) => {
+                let total = search.total();
+                if total > 0 {
+                    search.current = if forward {
+                        (search.current + 1) % total
+                    } else {
+                        (search.current + total - 1) % total
+                    };
+                }
+                true
+            }
+            None => false,
+        })
+        .unwrap_or(false);
+    if active {
+        show_current_match(siv);
+    }
+    active
+}
+
+fn show_current_match(siv: &mut Cursive) {
+    let message_width = siv
+        .find_name::<ScrollView<NamedView<TextView>>>("message_scroll")
+        .map_or(0, |scroll| scroll.content_viewport().width());
+    let target = siv
+        .with_user_data(|state: &mut ViewState| {
+            let message = &state.message;
+            let search = state.search.as_mut()?;
+            search.current = search.current.min(search.total().saturating_sub(1));
+            let current = search.current;
+            match search.message_matches.get(current) {
+                Some(position) => Some((
+                    "message_scroll",
+                    wrapped_row(message, message_width, *position),
+                )),
+                None => search
+                    .diff_lines
+                    .get(current - search.message_matches.len())
+                    .map(|line| ("diff_scroll", *line)),
+            }
+        })
+        .flatten();
+    if let Some((name, line)) = target {
+        scroll_to(siv, name, line);
+    }
+    show_search_state(siv);
+}
+
+/// Shows the query and the number of matches in the title of the diff panel.
+fn show_search_state(siv: &mut Cursive) {
+    let title = siv
+        .with_user_data(|state: &mut ViewState| match &state.search {
+            Some(search) if search.total() == 0 => {
+                format!("Diff | no matches for '{}'", search.query)
+            }
+            Some(search) => format!(
+                "Diff | match {}/{} for '{}'",
+                search.current + 1,
+                search.total(),
+                search.query
+            ),
+            None => "Diff".to_string(),
+        })
+        .unwrap_or_else(|| "Diff".to_string());
+    if let Some(mut panel) = siv.find_name::<DiffPanel>("diff_panel") {
+        panel.set_title(title);
+    }
+}
+
+/// Wraps the diff, so that it can be found by the view functions.
+pub fn diff_panel(content: SpannedString<Style>) -> NamedView<DiffPanel> {
+    Panel::new(
+        TextView::new(content)
+            .no_wrap()
+            .with_name("diff_field")
+            .scrollable()
+            .scroll_x(true)
+            .scroll_y(true)
+            .with_name("diff_scroll"),
+    )
+    .title("Diff")
+    .title_position(HAlign::Left)
+    .with_name("diff_panel")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_rows_of_positions() {
+        let text = SpannedString::<Style>::plain("short\n\nthe third line wraps here\nlast");
+        let row = |pattern: &str| wrapped_row(&text, 10, text.source().find(pattern).unwrap());
+        assert_eq!(row("short"), 0);
+        assert_eq!(row("third"), 2);
+        assert_eq!(row("wraps"), 3);
+        assert_eq!(row("here"), 4);
+        assert_eq!(row("last"), 5);
+        // without wrapping the rows are the lines
+        assert_eq!(
+            wrapped_row(&text, 80, text.source().find("here").unwrap()),
+            2
+        );
+    }
+}